@@ -0,0 +1,190 @@
+use serde::{Serialize, Deserialize};
+use crate::config;
+
+// how many tiles fit on screen to begin with, before the window is resized
+pub const DEFAULT_WIDTH: i32 = 33;
+pub const DEFAULT_HEIGHT: i32 = 25;
+
+/**
+ * How the camera follows its target. Any fields left out of a camera file use the default
+ */
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct CameraSettings {
+  // how much of the way to the target the camera is left each tick, from 0 (snap straight to it) to just under 1
+  pub smoothing: f32,
+  // how far in tiles the target can get from the middle of the screen before the camera starts to follow
+  pub dead_zone_x: f32,
+  pub dead_zone_y: f32,
+  // the edges of the world as (min x, min y, max x, max y) in tiles, which the camera won't show past
+  pub bounds: Option<(f32, f32, f32, f32)>
+}
+
+impl Default for CameraSettings {
+  fn default () -> Self {
+    CameraSettings {
+      smoothing: 0.8,
+      dead_zone_x: 1.,
+      dead_zone_y: 1.,
+      bounds: None
+    }
+  }
+}
+
+impl CameraSettings {
+
+  pub fn from_yaml (yaml: &str) -> Result<Self, serde_yaml::Error> {
+    serde_yaml::from_str(yaml)
+  }
+
+  // read camera settings from a file, using the defaults if there isn't one
+  pub fn load_or_default (path: &str) -> Self {
+    config::load_yaml_or_default(path, "the default camera settings")
+  }
+
+}
+
+/**
+ * The part of the world that's on screen. The camera is kept separate from the player so that it can lag behind
+ * them, and so that changing how many tiles fit on screen doesn't move anything in the world
+ */
+#[derive(Clone)]
+pub struct Camera {
+  pub x: f32, // the middle of the view, in world coordinates
+  pub y: f32,
+  pub prev_x: f32, // where the middle was on the tick before, for drawing between ticks
+  pub prev_y: f32,
+  pub width: i32, // how many tiles fit across the screen
+  pub height: i32,
+  pub settings: CameraSettings
+}
+
+impl Camera {
+
+  pub fn new (width: i32, height: i32) -> Self {
+    Camera { x: 0., y: 0., prev_x: 0., prev_y: 0., width, height, settings: CameraSettings::default() }
+  }
+
+  // change how many tiles fit on screen, keeping the same middle
+  pub fn resize (&mut self, width: i32, height: i32) {
+    self.width = width;
+    self.height = height;
+    self.clamp();
+  }
+
+  // jump straight to a position, for when the target teleports instead of walking there
+  pub fn snap_to (&mut self, x: f32, y: f32) {
+    self.x = x;
+    self.y = y;
+    self.clamp();
+    self.prev_x = self.x;
+    self.prev_y = self.y;
+  }
+
+  /**
+   * Move towards a target, once per tick. The target can move around inside the dead zone without the camera
+   * moving, and past it the camera eases towards keeping the target on the edge of the dead zone
+   */
+  pub fn follow (&mut self, target_x: f32, target_y: f32) {
+    self.prev_x = self.x;
+    self.prev_y = self.y;
+    let goal_x = target_x.clamp(self.x - self.settings.dead_zone_x, self.x + self.settings.dead_zone_x);
+    let goal_y = target_y.clamp(self.y - self.settings.dead_zone_y, self.y + self.settings.dead_zone_y);
+    // however far the target is past the edge of the dead zone is how far the camera has to catch up
+    let smoothing = self.settings.smoothing.clamp(0., 0.99);
+    self.x += (target_x - goal_x) * (1. - smoothing);
+    self.y += (target_y - goal_y) * (1. - smoothing);
+    self.clamp();
+  }
+
+  // the camera part of the way between where it was last tick and where it is now, to draw with
+  pub fn interpolated (&self, alpha: f32) -> Camera {
+    Camera {
+      x: self.prev_x + (self.x - self.prev_x) * alpha,
+      y: self.prev_y + (self.y - self.prev_y) * alpha,
+      ..self.clone()
+    }
+  }
+
+  // keep the view inside the world's bounds, centring it if the world is smaller than the screen
+  fn clamp (&mut self) {
+    if let Some((min_x, min_y, max_x, max_y)) = self.settings.bounds {
+      let (half_width, half_height) = (self.width as f32 / 2., self.height as f32 / 2.);
+      self.x = if max_x - min_x > self.width as f32 { self.x.clamp(min_x + half_width, max_x - half_width) } else { (min_x + max_x) / 2. };
+      self.y = if max_y - min_y > self.height as f32 { self.y.clamp(min_y + half_height, max_y - half_height) } else { (min_y + max_y) / 2. };
+    }
+  }
+
+  // the world position of the top left corner of the screen
+  pub fn top_left (&self) -> (f32, f32) {
+    (self.x - self.width as f32 / 2., self.y - self.height as f32 / 2.)
+  }
+
+  // where a world position is on screen, in the -1.0 to 1.0 coordinate space with y going up
+  pub fn to_screen (&self, x: f32, y: f32) -> [f32; 2] {
+    [(x - self.x) * 2. / self.width as f32, (self.y - y) * 2. / self.height as f32]
+  }
+
+  // the world position under a point on the screen, in the -1.0 to 1.0 coordinate space
+  pub fn to_world (&self, screen_pos: [f32; 2]) -> (f32, f32) {
+    (self.x + screen_pos[0] * self.width as f32 / 2., self.y - screen_pos[1] * self.height as f32 / 2.)
+  }
+
+  // how far to shift the tiles for the part of a tile the camera is past the edge of one, for smooth scrolling
+  pub fn tile_offset (&self) -> [f32; 2] {
+    let (left, top) = self.top_left();
+    // rem_euclid keeps the offset positive when the camera is at negative coordinates
+    [left.rem_euclid(1.) * 2. / self.width as f32, top.rem_euclid(1.) * 2. / self.height as f32]
+  }
+
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn the_camera_stays_put_inside_the_dead_zone () {
+    let mut camera = Camera::new(DEFAULT_WIDTH, DEFAULT_HEIGHT);
+    for (x, y) in [(0.5, 0.), (-1., 1.), (0.9, -0.9)] {
+      camera.follow(x, y);
+      assert_eq!((camera.x, camera.y), (0., 0.));
+    }
+  }
+
+  #[test]
+  fn the_camera_closes_in_at_the_smoothing_rate () {
+    let mut camera = Camera::new(DEFAULT_WIDTH, DEFAULT_HEIGHT);
+    // the target is 10 tiles past the edge of the dead zone, and each tick leaves 80% of that to go
+    for tick in 1..=10 {
+      camera.follow(11., 0.);
+      let behind = 11. - camera.settings.dead_zone_x - camera.x;
+      assert!((behind - 10. * 0.8_f32.powi(tick)).abs() < 0.001, "{} behind after {} ticks", behind, tick);
+    }
+    assert_eq!(camera.y, 0.);
+    // no smoothing catches up straight away
+    camera.settings.smoothing = 0.;
+    camera.follow(-20., 5.);
+    assert_eq!((camera.x, camera.y), (-19., 4.));
+  }
+
+  #[test]
+  fn the_camera_never_goes_past_the_bounds () {
+    let mut camera = Camera::new(DEFAULT_WIDTH, DEFAULT_HEIGHT);
+    camera.settings.bounds = Some((-20., -20., 20., 20.));
+    // half the screen is 16.5 by 12.5 tiles
+    for tick in 0..200 {
+      let target = if tick < 100 { (100., 100.) } else { (-100., -100.) };
+      camera.follow(target.0, target.1);
+      assert!(camera.x.abs() <= 3.5 && camera.y.abs() <= 7.5, "camera at ({}, {})", camera.x, camera.y);
+    }
+    assert_eq!((camera.x, camera.y), (-3.5, -7.5));
+    camera.snap_to(50., -50.);
+    assert_eq!((camera.x, camera.y), (3.5, -7.5));
+    // a world smaller than the screen is kept in the middle
+    camera.settings.bounds = Some((0., 0., 10., 10.));
+    camera.follow(100., 100.);
+    assert_eq!((camera.x, camera.y), (5., 5.));
+  }
+
+}
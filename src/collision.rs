@@ -0,0 +1,175 @@
+use crate::tiles;
+use crate::world;
+
+// how close two boxes have to be to count as touching, so rounding doesn't let things slip into each other
+const EPSILON: f32 = 0.0001;
+
+/**
+ * An axis-aligned box in world coordinates, where each tile is one unit across
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Aabb {
+  pub min_x: f32,
+  pub min_y: f32,
+  pub max_x: f32,
+  pub max_y: f32
+}
+
+impl Aabb {
+
+  pub fn new (min_x: f32, min_y: f32, max_x: f32, max_y: f32) -> Self {
+    Aabb { min_x, min_y, max_x, max_y }
+  }
+
+  // a box from its centre and half of its width and height
+  pub fn around (x: f32, y: f32, half_width: f32, half_height: f32) -> Self {
+    Aabb::new(x - half_width, y - half_height, x + half_width, y + half_height)
+  }
+
+  // where a tile's collision box is when the tile is at a position
+  pub fn from_tile (tile_x: i32, tile_y: i32, collision: &tiles::CollisionBox) -> Self {
+    let (x, y) = (tile_x as f32 + collision.x, tile_y as f32 + collision.y);
+    Aabb::new(x, y, x + collision.width, y + collision.height)
+  }
+
+  // whether two boxes share some area. boxes that are only touching don't overlap
+  pub fn overlaps (&self, other: &Aabb) -> bool {
+    self.min_x < other.max_x - EPSILON && self.max_x > other.min_x + EPSILON
+      && self.min_y < other.max_y - EPSILON && self.max_y > other.min_y + EPSILON
+  }
+
+  pub fn translate (&self, x: f32, y: f32) -> Aabb {
+    Aabb::new(self.min_x + x, self.min_y + y, self.max_x + x, self.max_y + y)
+  }
+
+  // the area the box passes through when it moves
+  pub fn swept (&self, x: f32, y: f32) -> Aabb {
+    Aabb::new(self.min_x + x.min(0.), self.min_y + y.min(0.), self.max_x + x.max(0.), self.max_y + y.max(0.))
+  }
+
+}
+
+/**
+ * The collision boxes of every solid tile that overlaps an area. Big objects can block tiles other than the
+ * one they're on, so tiles are checked as far out as the biggest object reaches
+ */
+pub fn colliders (world: &mut world::World, area: &Aabb) -> Vec<Aabb> {
+  let registry = tiles::registry();
+  let (reach_x, reach_y) = (registry.max_width as i32, registry.max_height as i32);
+  let mut boxes: Vec<Aabb> = Vec::new();
+  for tile_x in (area.min_x.floor() as i32 - reach_x)..=(area.max_x.floor() as i32 + reach_x) {
+    for tile_y in (area.min_y.floor() as i32 - reach_y)..=(area.max_y.floor() as i32 + reach_y) {
+      let tile = world.get_tile(tile_x, tile_y);
+      if !tile.solid {
+        continue;
+      }
+      let collider = Aabb::from_tile(tile_x, tile_y, &tile.collision);
+      if collider.overlaps(area) {
+        boxes.push(collider);
+      }
+    }
+  }
+  boxes
+}
+
+// how far a box can move along the x axis before it hits one of the colliders
+fn clamp_x (colliders: &[Aabb], aabb: &Aabb, mut x: f32) -> f32 {
+  for collider in colliders {
+    // only things level with the box can be run into. anything the box is already stuck inside is ignored so it can get out
+    if collider.max_y - EPSILON <= aabb.min_y || collider.min_y + EPSILON >= aabb.max_y || collider.overlaps(aabb) {
+      continue;
+    }
+    if x > 0. && collider.min_x >= aabb.max_x - EPSILON {
+      x = x.min((collider.min_x - aabb.max_x).max(0.));
+    } else if x < 0. && collider.max_x <= aabb.min_x + EPSILON {
+      x = x.max((collider.max_x - aabb.min_x).min(0.));
+    }
+  }
+  x
+}
+
+// the same as clamp_x, along the y axis
+fn clamp_y (colliders: &[Aabb], aabb: &Aabb, mut y: f32) -> f32 {
+  for collider in colliders {
+    if collider.max_x - EPSILON <= aabb.min_x || collider.min_x + EPSILON >= aabb.max_x || collider.overlaps(aabb) {
+      continue;
+    }
+    if y > 0. && collider.min_y >= aabb.max_y - EPSILON {
+      y = y.min((collider.min_y - aabb.max_y).max(0.));
+    } else if y < 0. && collider.max_y <= aabb.min_y + EPSILON {
+      y = y.max((collider.max_y - aabb.min_y).min(0.));
+    }
+  }
+  y
+}
+
+/**
+ * Move a box through the world, stopping it against anything solid in the way. Each axis is moved separately,
+ * so when one direction is blocked the box keeps sliding along the other. Returns how far it actually moved
+ */
+pub fn sweep (world: &mut world::World, aabb: &Aabb, x: f32, y: f32) -> (f32, f32) {
+  let colliders = colliders(world, &aabb.swept(x, y));
+  let moved_x = clamp_x(&colliders, aabb, x);
+  let moved_y = clamp_y(&colliders, &aabb.translate(moved_x, 0.), y);
+  (moved_x, moved_y)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  // the box around the player's feet, standing in the middle of a tile
+  fn feet (x: f32, y: f32) -> Aabb {
+    Aabb::around(x, y, 0.3, 0.2)
+  }
+
+  fn grass_world () -> world::World {
+    world::World::flat(tiles::registry().tile("grass").id)
+  }
+
+  #[test]
+  fn stops_flush_against_a_wall () {
+    let mut world = grass_world();
+    world.set_tile(3, 0, tiles::registry().tile("wood_wall").id);
+    let (moved_x, moved_y) = sweep(&mut world, &feet(0.5, 0.5), 5., 0.);
+    assert!((moved_x - 2.2).abs() < 0.001, "moved {}", moved_x);
+    assert_eq!(moved_y, 0.);
+    // and can't be pushed any further into it
+    let (moved_x, _) = sweep(&mut world, &feet(0.5 + moved_x, 0.5), 1., 0.);
+    assert!(moved_x.abs() < 0.001);
+  }
+
+  #[test]
+  fn slides_along_a_wall_on_a_diagonal () {
+    let mut world = grass_world();
+    for y in -5..5 {
+      world.set_tile(3, y, tiles::registry().tile("wood_wall").id);
+    }
+    let (moved_x, moved_y) = sweep(&mut world, &feet(0.5, 0.5), 4., 2.);
+    assert!((moved_x - 2.2).abs() < 0.001);
+    assert_eq!(moved_y, 2.);
+  }
+
+  #[test]
+  fn walks_behind_a_tree_but_not_through_its_trunk () {
+    let mut world = grass_world();
+    world.set_tile(0, 0, tiles::registry().tile("tree").id);
+    // the leaves reach up three tiles above the trunk
+    let (moved_x, _) = sweep(&mut world, &feet(-2.5, -1.5), 5., 0.);
+    assert_eq!(moved_x, 5.);
+    // the trunk covers the bottom half of its tile
+    let (moved_x, _) = sweep(&mut world, &feet(-2.5, 0.75), 5., 0.);
+    assert!((moved_x - (0.125 - -2.2)).abs() < 0.001, "moved {}", moved_x);
+  }
+
+  #[test]
+  fn gets_out_of_a_box_its_already_inside () {
+    let mut world = grass_world();
+    world.set_tile(0, 0, tiles::registry().tile("wood_wall").id);
+    let (moved_x, moved_y) = sweep(&mut world, &feet(0.5, 0.5), 2., 0.);
+    assert_eq!((moved_x, moved_y), (2., 0.));
+    let (moved_x, moved_y) = sweep(&mut world, &feet(0.5, 0.5), 0., -2.);
+    assert_eq!((moved_x, moved_y), (0., -2.));
+  }
+
+}
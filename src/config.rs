@@ -0,0 +1,42 @@
+use std::fmt;
+use std::fs;
+use serde::de::DeserializeOwned;
+
+/**
+ * Read something from a yaml file next to the game, like the rules or the controls. A missing file quietly gives
+ * the fallback, since most players never make one, but a file that can't be read says why before falling back
+ */
+pub fn load_yaml_or<T, E: fmt::Display> (path: &str, what: &str, parse: impl FnOnce(&str) -> Result<T, E>, fallback: impl FnOnce() -> T) -> T {
+  match fs::read_to_string(path) {
+    Ok(yaml) => parse(&yaml).unwrap_or_else(|err| {
+      println!("Couldn't read {}, using {} instead: {}", path, what, err);
+      fallback()
+    }),
+    Err(_) => fallback()
+  }
+}
+
+// read a file that's nothing but the fields of something with defaults, using the defaults if there isn't one
+pub fn load_yaml_or_default<T: DeserializeOwned + Default> (path: &str, what: &str) -> T {
+  load_yaml_or(path, what, serde_yaml::from_str, T::default)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::rules::GameRules;
+
+  #[test]
+  fn missing_and_broken_files_fall_back () {
+    let path = std::env::temp_dir().join(format!("worldxplore-config-{}.yaml", std::process::id()));
+    let path = path.to_str().unwrap();
+    assert_eq!(load_yaml_or_default::<GameRules>(path, "the default rules"), GameRules::default());
+    fs::write(path, "hunger_rate: [").unwrap();
+    assert_eq!(load_yaml_or_default::<GameRules>(path, "the default rules"), GameRules::default());
+    fs::write(path, "hunger_rate: 0.5\n").unwrap();
+    let rules: GameRules = load_yaml_or_default(path, "the default rules");
+    fs::remove_file(path).unwrap();
+    assert_eq!(rules, GameRules { hunger_rate: 0.5, ..GameRules::default() });
+  }
+
+}
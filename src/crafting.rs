@@ -0,0 +1,165 @@
+use std::fmt;
+use crate::inventory;
+use crate::tiles;
+use crate::world;
+
+// how many tiles away from the player's feet a crafting station can be and still be used
+pub const STATION_REACH: i32 = 3;
+
+/**
+ * Reasons a recipe can't be crafted
+ */
+#[derive(Debug)]
+pub enum CraftError {
+  MissingItem { item: String, needed: u32, have: u32 },
+  NoStation { station: String },
+  NoRoom { item: String }
+}
+
+impl fmt::Display for CraftError {
+  fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      CraftError::MissingItem { item, needed, have } => write!(f, "Needs {} {} but only have {}", needed, item, have),
+      CraftError::NoStation { station } => write!(f, "Needs a {} nearby", station),
+      CraftError::NoRoom { item } => write!(f, "No room for {}", item)
+    }
+  }
+}
+
+impl std::error::Error for CraftError {}
+
+// whether there's a tile with a name within reach of a position
+pub fn station_nearby (world: &mut world::World, x: i32, y: i32, station: &str) -> bool {
+  let station_id = match tiles::registry().tile_id(station) {
+    Some(id) => id,
+    None => return false
+  };
+  for tile_x in (x - STATION_REACH)..=(x + STATION_REACH) {
+    for tile_y in (y - STATION_REACH)..=(y + STATION_REACH) {
+      if world.get_tile_id(tile_x, tile_y) == station_id {
+        return true;
+      }
+    }
+  }
+  false
+}
+
+// check that the inventory has everything a recipe needs, without changing anything
+pub fn check_inputs (inventory: &inventory::Inventory, recipe: &tiles::RecipeDefinition) -> Result<(), CraftError> {
+  let registry = tiles::registry();
+  for input in &recipe.inputs {
+    let item = registry.item(&input.item);
+    let have = inventory.count(item.id);
+    if have < input.count {
+      return Err(CraftError::MissingItem { item: String::from(item.name), needed: input.count, have });
+    }
+  }
+  Ok(())
+}
+
+/**
+ * Craft a recipe for a player standing at a position. Either all of the inputs are used up and the output is added,
+ * or nothing in the inventory changes and the reason is returned
+ */
+pub fn craft<'a> (inventory: &mut inventory::Inventory<'a>, recipe: &tiles::RecipeDefinition, world: &mut world::World, x: i32, y: i32) -> Result<inventory::ItemStack<'a>, CraftError> {
+  let registry = tiles::registry();
+  if let Some(station) = &recipe.station {
+    if !station_nearby(world, x, y, station) {
+      let name = registry.tile_id(station).and_then(|id| registry.tile_definition(id)).map_or(station.as_str(), |tile| tile.display_name.as_str());
+      return Err(CraftError::NoStation { station: String::from(name) });
+    }
+  }
+  check_inputs(inventory, recipe)?;
+  // work on a copy, so that if the output doesn't fit the inputs aren't lost
+  let mut crafted = inventory.clone();
+  for input in &recipe.inputs {
+    crafted.remove(registry.item(&input.item).id, input.count);
+  }
+  let output = inventory::ItemStack::new(registry.item(&recipe.output), recipe.count);
+  if crafted.add(output).is_some() {
+    return Err(CraftError::NoRoom { item: String::from(output.item.name) });
+  }
+  *inventory = crafted;
+  Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::inventory::{Inventory, ItemStack};
+
+  fn recipe (output: &str) -> &'static tiles::RecipeDefinition {
+    tiles::registry().recipes().iter().find(|recipe| recipe.output == output).unwrap()
+  }
+
+  fn stack (name: &str, count: u32) -> ItemStack<'static> {
+    ItemStack::new(tiles::registry().item(name), count)
+  }
+
+  fn counts (inventory: &Inventory<'static>) -> Vec<Option<(&'static str, u32)>> {
+    inventory.slots.iter().map(|slot| slot.map(|stack| (stack.item.name, stack.count))).collect()
+  }
+
+  fn grass () -> world::World {
+    world::World::flat(tiles::registry().tile("grass").id)
+  }
+
+  #[test]
+  fn crafting_uses_up_the_inputs_and_adds_the_output () {
+    let mut inventory = Inventory::new(3);
+    inventory.add(stack("log", 3));
+    inventory.add(stack("stick", 4));
+    let output = craft(&mut inventory, recipe("campfire"), &mut grass(), 0, 0).unwrap();
+    assert_eq!((output.item.name, output.count), ("Campfire", 1));
+    assert_eq!(counts(&inventory), vec![Some(("Log", 1)), Some(("Stick", 1)), Some(("Campfire", 1))]);
+  }
+
+  #[test]
+  fn no_room_for_the_output_changes_nothing () {
+    // the log is only partly used up, so the planks have nowhere to go
+    let mut inventory = Inventory::new(1);
+    inventory.add(stack("log", 5));
+    match craft(&mut inventory, recipe("plank"), &mut grass(), 0, 0) {
+      Err(CraftError::NoRoom { item }) => assert_eq!(item, "Plank"),
+      other => panic!("expected no room, got {:?}", other)
+    }
+    assert_eq!(counts(&inventory), vec![Some(("Log", 5))]);
+  }
+
+  #[test]
+  fn stations_have_to_be_within_reach () {
+    let mut world = grass();
+    let mut inventory = Inventory::new(2);
+    inventory.add(stack("plank", 4));
+    assert!(matches!(craft(&mut inventory, recipe("wood_wall"), &mut world, 0, 0), Err(CraftError::NoStation { .. })));
+    world.set_tile(STATION_REACH + 1, 0, tiles::registry().tile("workbench").id);
+    assert!(!station_nearby(&mut world, 0, 0, "workbench"));
+    match craft(&mut inventory, recipe("wood_wall"), &mut world, 0, 0) {
+      Err(CraftError::NoStation { station }) => assert_eq!(station, "Workbench"),
+      other => panic!("expected no station, got {:?}", other)
+    }
+    assert_eq!(counts(&inventory), vec![Some(("Plank", 4)), None]);
+    // a step closer and it's in reach, diagonals included
+    world.set_tile(STATION_REACH, -STATION_REACH, tiles::registry().tile("workbench").id);
+    assert!(craft(&mut inventory, recipe("wood_wall"), &mut world, 0, 0).is_ok());
+    assert_eq!(counts(&inventory), vec![Some(("Plank", 2)), Some(("Wall", 1))]);
+  }
+
+  #[test]
+  fn stations_that_dont_exist_are_never_nearby () {
+    assert!(!station_nearby(&mut grass(), 0, 0, "no_such_tile"));
+  }
+
+  #[test]
+  fn not_enough_of_an_input_says_how_many () {
+    let mut inventory = Inventory::new(3);
+    inventory.add(stack("log", 2));
+    inventory.add(stack("stick", 1));
+    match craft(&mut inventory, recipe("campfire"), &mut grass(), 0, 0) {
+      Err(CraftError::MissingItem { item, needed, have }) => assert_eq!((item.as_str(), needed, have), ("Stick", 3, 1)),
+      other => panic!("expected a missing item, got {:?}", other)
+    }
+    assert_eq!(counts(&inventory), vec![Some(("Log", 2)), Some(("Stick", 1)), None]);
+  }
+
+}
@@ -0,0 +1,182 @@
+use winit::event::VirtualKeyCode;
+use crate::camera;
+use crate::gamepad;
+use crate::input::Action;
+use crate::player;
+use crate::rules;
+use crate::ui;
+use crate::world;
+use crate::worldgen;
+
+/**
+ * Something the player does. Input from the keyboard and mouse and input from a script both end up here,
+ * so the game plays the same with or without a window. Keys are turned into actions with the player's bindings
+ * before they get here
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum InputEvent {
+  ActionPressed(Action),
+  ActionReleased(Action), // only matters for actions that are held down, like moving and sprinting
+  Bind(Action, VirtualKeyCode), // a key was pressed on the controls screen for the action waiting for one
+  BindButton(Action, gamepad::Button),
+  Move(f32, f32), // a controller's stick was moved, to walk that way as fast as it's pushed
+  Type(char), // a character was typed, for naming a world. keys aren't turned into actions while a name is typed
+  Interact(i32, i32) // use a tile, like one that was clicked on
+}
+
+/**
+ * Input to play back without a window, as events and the tick each one happens on
+ */
+#[derive(Clone, Debug, Default)]
+pub struct Script {
+  pub events: Vec<(u64, InputEvent)>
+}
+
+impl Script {
+
+  pub fn new () -> Self {
+    Script { events: Vec::new() }
+  }
+
+  // add an event that happens some number of ticks after the script starts
+  pub fn at (mut self, tick: u64, event: InputEvent) -> Self {
+    self.events.push((tick, event));
+    self
+  }
+
+  // hold an action down for a number of ticks
+  pub fn hold (self, tick: u64, action: Action, ticks: u64) -> Self {
+    self.at(tick, InputEvent::ActionPressed(action)).at(tick + ticks, InputEvent::ActionReleased(action))
+  }
+
+}
+
+/**
+ * Everything that's being played: the world, and the player in it. The game only moves on when it's ticked,
+ * so it can be run by the windowed game, or on its own as fast as it can go
+ */
+pub struct Game<'a> {
+  pub world: world::World,
+  pub player: player::Player<'a>,
+  pub gamepad: gamepad::GamepadInput,
+  pub ticks: u64 // how many ticks have run since the game started
+}
+
+impl Game<'_> {
+
+  // generate a new world and put the player somewhere safe in it
+  pub fn new (seed: u32, settings: worldgen::WorldGenSettings, rules: rules::GameRules) -> Self {
+    let mut world = world::World::new(seed, settings);
+    let mut player = player::Player::new(camera::DEFAULT_WIDTH, camera::DEFAULT_HEIGHT, seed);
+    player.rules = rules;
+    player.spawn(&mut world);
+    Game { world, player, gamepad: gamepad::GamepadInput::default(), ticks: 0 }
+  }
+
+  // start in a world that's already been made, like a small handmade one, with the player on a tile
+  pub fn with_world (world: world::World, tile_x: i32, tile_y: i32) -> Self {
+    let mut player = player::Player::new(camera::DEFAULT_WIDTH, camera::DEFAULT_HEIGHT, world.seed);
+    player.move_to_tile(tile_x, tile_y);
+    player.spawn_point = (tile_x, tile_y);
+    Game { world, player, gamepad: gamepad::GamepadInput::default(), ticks: 0 }
+  }
+
+  pub fn tick (&mut self) {
+    self.player.tick(&mut self.world);
+    self.ticks += 1;
+  }
+
+  /**
+   * Do whatever an input event does. Returns whether any tiles changed, so a renderer knows to redraw them
+   */
+  pub fn handle (&mut self, event: InputEvent) -> bool {
+    let player = &mut self.player;
+    match event {
+      // moving around a menu picks the buttons in it, and interacting clicks the picked one
+      InputEvent::ActionPressed(Action::MoveUp | Action::MoveLeft) if player.paused => player.focus.previous(),
+      InputEvent::ActionPressed(Action::MoveDown | Action::MoveRight) if player.paused => player.focus.next(),
+      InputEvent::ActionPressed(Action::Interact) if player.paused => player.focus.activate = true,
+      // pausing while a world's being renamed stops renaming it instead
+      InputEvent::ActionPressed(Action::Pause) if player.world_list.renaming.is_some() => player.world_list.renaming = None,
+      InputEvent::ActionPressed(Action::Pause) => Game::toggle_menu(player, player::PauseType::Pause),
+      InputEvent::ActionPressed(Action::OpenInventory) => Game::toggle_menu(player, player::PauseType::Inventory),
+      InputEvent::ActionPressed(Action::OpenCrafting) => Game::toggle_menu(player, player::PauseType::Crafting),
+      InputEvent::ActionPressed(Action::ToggleBuilding) => player.building = !player.building,
+      InputEvent::ActionPressed(Action::Eat) => if !player.paused {
+        player.eat();
+      },
+      // use the tile in front of the player
+      InputEvent::ActionPressed(Action::Interact) => {
+        let (tile_x, tile_y) = player.target_tile();
+        return player.interact(&mut self.world, tile_x, tile_y);
+      },
+      InputEvent::ActionPressed(action) => match action.hotbar_slot() {
+        Some(slot) => player.select(slot),
+        None => player.action_pressed(action)
+      },
+      InputEvent::ActionReleased(action) => player.action_released(action),
+      // pressing pause stops waiting for a key instead of binding it
+      InputEvent::Bind(action, key) => {
+        if !player.bindings.keys(Action::Pause).contains(&key) {
+          player.bindings.bind(action, key);
+        }
+        player.rebinding = None;
+      },
+      InputEvent::BindButton(action, button) => {
+        if !player.gamepad_bindings.keys(Action::Pause).contains(&button) {
+          player.gamepad_bindings.bind(action, button);
+        }
+        player.rebinding = None;
+      },
+      InputEvent::Move(stick_x, stick_y) => player.tilt(stick_x, stick_y),
+      InputEvent::Type(character) => player.type_character(character),
+      InputEvent::Interact(tile_x, tile_y) => if !player.paused {
+        return player.interact(&mut self.world, tile_x, tile_y);
+      }
+    }
+    false
+  }
+
+  // menus can't be opened or closed while dead, only the death screen can bring the player back
+  fn toggle_menu (player: &mut player::Player, pause_type: player::PauseType) {
+    if player.dead {
+      return;
+    }
+    if pause_type == player::PauseType::Crafting {
+      player.crafting_message = String::new();
+    }
+    if pause_type == player::PauseType::Pause {
+      player.save_message = String::new();
+    }
+    player.pause_type = pause_type;
+    player.paused = !player.paused;
+    player.rebinding = None;
+    player.world_list.renaming = None;
+    player.focus = ui::Focus::default();
+  }
+
+  /**
+   * Read a controller and do whatever it's doing, the same as if it were input events. A controller that's been
+   * unplugged lets go of everything. Returns whether any tiles changed
+   */
+  pub fn poll_gamepad (&mut self, gamepad: &mut dyn gamepad::Gamepad) -> bool {
+    let state = gamepad.state().unwrap_or_default();
+    let events = self.gamepad.events(&state, &self.player.gamepad_bindings, self.player.rebinding);
+    let mut changed = false;
+    for event in events {
+      changed |= self.handle(event);
+    }
+    changed
+  }
+
+  // play a script for a number of ticks, with each event happening just before the tick it's for
+  pub fn run (&mut self, script: &Script, ticks: u64) {
+    for tick in 0..ticks {
+      for (_, event) in script.events.iter().filter(|(event_tick, _)| *event_tick == tick) {
+        self.handle(*event);
+      }
+      self.tick();
+    }
+  }
+
+}
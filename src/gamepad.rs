@@ -0,0 +1,221 @@
+use std::collections::HashSet;
+use serde::{Serialize, Deserialize};
+use crate::game::InputEvent;
+use crate::input;
+
+// how far the stick has to be pushed, from 0 to 1, before it does anything. worn sticks don't quite centre
+pub const DEFAULT_DEAD_ZONE: f32 = 0.2;
+
+/**
+ * A button on a controller, named by where it is rather than what's printed on it, so South is A on an Xbox
+ * controller and Cross on a PlayStation one
+ */
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Button {
+  South,
+  East,
+  North,
+  West,
+  LeftBumper,
+  RightBumper,
+  LeftTrigger,
+  RightTrigger,
+  Select,
+  Start,
+  LeftStick, // pressing the stick in
+  RightStick,
+  DPadUp,
+  DPadDown,
+  DPadLeft,
+  DPadRight
+}
+
+/**
+ * Everything a controller is doing at one moment
+ */
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct GamepadState {
+  pub buttons: HashSet<Button>, // the buttons being held down
+  // where the left stick is pushed, from -1 to 1 on each axis. y goes down the world, so -1 is pushed up
+  pub stick_x: f32,
+  pub stick_y: f32
+}
+
+/**
+ * Somewhere to read a controller from. The windowed game reads real controllers, and anything else can give the
+ * game whatever input it likes
+ */
+pub trait Gamepad {
+  // what the controller is doing now, or nothing if there isn't one plugged in
+  fn state (&mut self) -> Option<GamepadState>;
+}
+
+/**
+ * A controller that's pressed and tilted by hand, for driving the game from tests
+ */
+#[derive(Clone, Debug, Default)]
+pub struct FakeGamepad {
+  pub state: GamepadState,
+  pub connected: bool
+}
+
+impl FakeGamepad {
+
+  pub fn new () -> Self {
+    FakeGamepad { state: GamepadState::default(), connected: true }
+  }
+
+  pub fn press (&mut self, button: Button) {
+    self.state.buttons.insert(button);
+  }
+
+  pub fn release (&mut self, button: Button) {
+    self.state.buttons.remove(&button);
+  }
+
+  pub fn tilt (&mut self, stick_x: f32, stick_y: f32) {
+    self.state.stick_x = stick_x;
+    self.state.stick_y = stick_y;
+  }
+
+}
+
+impl Gamepad for FakeGamepad {
+  fn state (&mut self) -> Option<GamepadState> {
+    if self.connected { Some(self.state.clone()) } else { None }
+  }
+}
+
+/**
+ * Turns what a controller is doing into input events, by comparing it to what it was doing last time
+ */
+#[derive(Clone, Debug)]
+pub struct GamepadInput {
+  pub dead_zone: f32,
+  pub last_state: GamepadState
+}
+
+impl Default for GamepadInput {
+  fn default () -> Self {
+    GamepadInput { dead_zone: DEFAULT_DEAD_ZONE, last_state: GamepadState::default() }
+  }
+}
+
+impl GamepadInput {
+
+  /**
+   * Take out the dead zone from the middle of the stick, and stretch what's left so moving just past the dead zone
+   * is a slow walk and pushing it all the way is full speed
+   */
+  pub fn apply_dead_zone (&self, stick_x: f32, stick_y: f32) -> (f32, f32) {
+    let length = (stick_x * stick_x + stick_y * stick_y).sqrt();
+    if length <= self.dead_zone {
+      return (0., 0.);
+    }
+    let scale = ((length - self.dead_zone) / (1. - self.dead_zone)).min(1.) / length;
+    (stick_x * scale, stick_y * scale)
+  }
+
+  /**
+   * The events for everything that's changed since the last state. Buttons are turned into actions with the
+   * bindings, unless the controls screen is waiting for a button to bind
+   */
+  pub fn events (&mut self, state: &GamepadState, bindings: &input::Bindings<Button>, rebinding: Option<input::Action>) -> Vec<InputEvent> {
+    let mut events: Vec<InputEvent> = Vec::new();
+    let pressed: Vec<Button> = state.buttons.difference(&self.last_state.buttons).cloned().collect();
+    let released: Vec<Button> = self.last_state.buttons.difference(&state.buttons).cloned().collect();
+    match rebinding {
+      Some(action) => if let Some(button) = pressed.first() {
+        events.push(InputEvent::BindButton(action, *button));
+      },
+      None => for (action, buttons) in bindings.keys.iter() {
+        if buttons.iter().any(|button| pressed.contains(button)) {
+          events.push(InputEvent::ActionPressed(*action));
+        }
+      }
+    }
+    // an action is only let go of once none of its buttons are held
+    for (action, buttons) in bindings.keys.iter() {
+      if buttons.iter().any(|button| released.contains(button)) && !buttons.iter().any(|button| state.buttons.contains(button)) {
+        events.push(InputEvent::ActionReleased(*action));
+      }
+    }
+    let stick = self.apply_dead_zone(state.stick_x, state.stick_y);
+    if stick != self.apply_dead_zone(self.last_state.stick_x, self.last_state.stick_y) {
+      events.push(InputEvent::Move(stick.0, stick.1));
+    }
+    self.last_state = state.clone();
+    events
+  }
+
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use input::Action;
+
+  #[test]
+  fn the_dead_zone_is_cut_out_and_the_rest_stretched () {
+    let input = GamepadInput::default();
+    assert_eq!(input.apply_dead_zone(0.1, -0.1), (0., 0.));
+    assert_eq!(input.apply_dead_zone(0.2, 0.), (0., 0.));
+    let (x, y) = input.apply_dead_zone(0.6, 0.);
+    assert!((x - 0.5).abs() < 0.0001 && y == 0.);
+    let (x, y) = input.apply_dead_zone(0., -1.);
+    assert!(x == 0. && (y + 1.).abs() < 0.0001);
+    // pushing into a corner doesn't go faster than pushing straight
+    let (x, y) = input.apply_dead_zone(1., 1.);
+    assert!(((x * x + y * y).sqrt() - 1.).abs() < 0.0001);
+  }
+
+  #[test]
+  fn buttons_are_pressed_and_released_once () {
+    let bindings: input::Bindings<Button> = input::Bindings::default();
+    let mut input = GamepadInput::default();
+    let mut pad = FakeGamepad::new();
+    pad.press(Button::South);
+    assert_eq!(input.events(&pad.state().unwrap(), &bindings, None), vec![InputEvent::ActionPressed(Action::Interact)]);
+    // held down, nothing new happens
+    assert_eq!(input.events(&pad.state().unwrap(), &bindings, None), vec![]);
+    pad.release(Button::South);
+    assert_eq!(input.events(&pad.state().unwrap(), &bindings, None), vec![InputEvent::ActionReleased(Action::Interact)]);
+  }
+
+  #[test]
+  fn actions_with_two_buttons_are_held_until_both_are_let_go () {
+    let bindings: input::Bindings<Button> = input::Bindings::default();
+    let mut input = GamepadInput::default();
+    let mut pad = FakeGamepad::new();
+    pad.press(Button::LeftStick);
+    pad.press(Button::LeftTrigger);
+    assert_eq!(input.events(&pad.state().unwrap(), &bindings, None), vec![InputEvent::ActionPressed(Action::Sprint)]);
+    pad.release(Button::LeftStick);
+    assert_eq!(input.events(&pad.state().unwrap(), &bindings, None), vec![]);
+    pad.release(Button::LeftTrigger);
+    assert_eq!(input.events(&pad.state().unwrap(), &bindings, None), vec![InputEvent::ActionReleased(Action::Sprint)]);
+  }
+
+  #[test]
+  fn moving_the_stick_sends_a_move () {
+    let bindings: input::Bindings<Button> = input::Bindings::default();
+    let mut input = GamepadInput::default();
+    let mut pad = FakeGamepad::new();
+    pad.tilt(0.05, 0.);
+    assert_eq!(input.events(&pad.state().unwrap(), &bindings, None), vec![]);
+    pad.tilt(1., 0.);
+    assert_eq!(input.events(&pad.state().unwrap(), &bindings, None), vec![InputEvent::Move(1., 0.)]);
+    pad.tilt(0., 0.);
+    assert_eq!(input.events(&pad.state().unwrap(), &bindings, None), vec![InputEvent::Move(0., 0.)]);
+  }
+
+  #[test]
+  fn a_button_is_bound_instead_while_rebinding () {
+    let bindings: input::Bindings<Button> = input::Bindings::default();
+    let mut input = GamepadInput::default();
+    let mut pad = FakeGamepad::new();
+    pad.press(Button::South);
+    assert_eq!(input.events(&pad.state().unwrap(), &bindings, Some(Action::Eat)), vec![InputEvent::BindButton(Action::Eat, Button::South)]);
+  }
+
+}
@@ -0,0 +1,254 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use serde::{Serialize, Deserialize};
+use serde::de::DeserializeOwned;
+use winit::event::VirtualKeyCode;
+use crate::config;
+use crate::gamepad;
+
+// the most keys (or buttons) the controls screen will keep bound to one action. a controls file can list more
+pub const MAX_KEYS_PER_ACTION: usize = 2;
+
+/**
+ * Something the player can do with a key or controller button. Keys and buttons are only ever looked at to work
+ * out which of these they're bound to, so the rest of the game doesn't care what's being used to play it
+ */
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Action {
+  MoveUp,
+  MoveDown,
+  MoveLeft,
+  MoveRight,
+  Sprint,
+  Interact,
+  Eat,
+  ToggleBuilding,
+  OpenInventory,
+  OpenCrafting,
+  Pause,
+  Hotbar1,
+  Hotbar2,
+  Hotbar3,
+  Hotbar4,
+  Hotbar5,
+  Hotbar6,
+  Hotbar7,
+  Hotbar8,
+  Hotbar9
+}
+
+impl Action {
+
+  // every action, in the order the controls screen shows them
+  pub const ALL: [Action; 20] = [
+    Action::MoveUp, Action::MoveDown, Action::MoveLeft, Action::MoveRight, Action::Sprint,
+    Action::Interact, Action::Eat, Action::ToggleBuilding, Action::OpenInventory, Action::OpenCrafting, Action::Pause,
+    Action::Hotbar1, Action::Hotbar2, Action::Hotbar3, Action::Hotbar4, Action::Hotbar5,
+    Action::Hotbar6, Action::Hotbar7, Action::Hotbar8, Action::Hotbar9
+  ];
+
+  // the name shown on the controls screen
+  pub fn name (&self) -> &'static str {
+    match self {
+      Action::MoveUp => "Move Up",
+      Action::MoveDown => "Move Down",
+      Action::MoveLeft => "Move Left",
+      Action::MoveRight => "Move Right",
+      Action::Sprint => "Sprint",
+      Action::Interact => "Interact",
+      Action::Eat => "Eat",
+      Action::ToggleBuilding => "Building",
+      Action::OpenInventory => "Inventory",
+      Action::OpenCrafting => "Crafting",
+      Action::Pause => "Pause",
+      Action::Hotbar1 => "Slot 1",
+      Action::Hotbar2 => "Slot 2",
+      Action::Hotbar3 => "Slot 3",
+      Action::Hotbar4 => "Slot 4",
+      Action::Hotbar5 => "Slot 5",
+      Action::Hotbar6 => "Slot 6",
+      Action::Hotbar7 => "Slot 7",
+      Action::Hotbar8 => "Slot 8",
+      Action::Hotbar9 => "Slot 9"
+    }
+  }
+
+  // the inventory slot a hotbar action holds, counting from 0
+  pub fn hotbar_slot (&self) -> Option<usize> {
+    match self {
+      Action::Hotbar1 => Some(0),
+      Action::Hotbar2 => Some(1),
+      Action::Hotbar3 => Some(2),
+      Action::Hotbar4 => Some(3),
+      Action::Hotbar5 => Some(4),
+      Action::Hotbar6 => Some(5),
+      Action::Hotbar7 => Some(6),
+      Action::Hotbar8 => Some(7),
+      Action::Hotbar9 => Some(8),
+      _ => None
+    }
+  }
+
+  // the direction a movement action moves in, as a tile offset
+  pub fn direction (&self) -> Option<(i32, i32)> {
+    match self {
+      Action::MoveUp => Some((0, -1)),
+      Action::MoveDown => Some((0, 1)),
+      Action::MoveLeft => Some((-1, 0)),
+      Action::MoveRight => Some((1, 0)),
+      _ => None
+    }
+  }
+
+}
+
+/**
+ * Which keys, or which controller buttons, do each action. An action can have any number of them, and any actions
+ * left out of a controls file keep their defaults
+ */
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(transparent)]
+pub struct Bindings<B = VirtualKeyCode> {
+  pub keys: BTreeMap<Action, Vec<B>>
+}
+
+impl Default for Bindings<VirtualKeyCode> {
+  fn default () -> Self {
+    let keys = Action::ALL.iter().map(|action| {
+      let keys = match action {
+        Action::MoveUp => vec![VirtualKeyCode::W, VirtualKeyCode::Up],
+        Action::MoveDown => vec![VirtualKeyCode::S, VirtualKeyCode::Down],
+        Action::MoveLeft => vec![VirtualKeyCode::A, VirtualKeyCode::Left],
+        Action::MoveRight => vec![VirtualKeyCode::D, VirtualKeyCode::Right],
+        Action::Sprint => vec![VirtualKeyCode::LShift],
+        Action::Interact => vec![VirtualKeyCode::F],
+        Action::Eat => vec![VirtualKeyCode::Q],
+        Action::ToggleBuilding => vec![VirtualKeyCode::B],
+        Action::OpenInventory => vec![VirtualKeyCode::E],
+        Action::OpenCrafting => vec![VirtualKeyCode::C],
+        Action::Pause => vec![VirtualKeyCode::Escape],
+        Action::Hotbar1 => vec![VirtualKeyCode::Key1],
+        Action::Hotbar2 => vec![VirtualKeyCode::Key2],
+        Action::Hotbar3 => vec![VirtualKeyCode::Key3],
+        Action::Hotbar4 => vec![VirtualKeyCode::Key4],
+        Action::Hotbar5 => vec![VirtualKeyCode::Key5],
+        Action::Hotbar6 => vec![VirtualKeyCode::Key6],
+        Action::Hotbar7 => vec![VirtualKeyCode::Key7],
+        Action::Hotbar8 => vec![VirtualKeyCode::Key8],
+        Action::Hotbar9 => vec![VirtualKeyCode::Key9]
+      };
+      (*action, keys)
+    }).collect();
+    Bindings { keys }
+  }
+}
+
+impl Default for Bindings<gamepad::Button> {
+  fn default () -> Self {
+    let keys = Action::ALL.iter().map(|action| {
+      let buttons = match action {
+        Action::MoveUp => vec![gamepad::Button::DPadUp],
+        Action::MoveDown => vec![gamepad::Button::DPadDown],
+        Action::MoveLeft => vec![gamepad::Button::DPadLeft],
+        Action::MoveRight => vec![gamepad::Button::DPadRight],
+        Action::Sprint => vec![gamepad::Button::LeftStick, gamepad::Button::LeftTrigger],
+        Action::Interact => vec![gamepad::Button::South],
+        Action::Eat => vec![gamepad::Button::West],
+        Action::ToggleBuilding => vec![gamepad::Button::North],
+        Action::OpenInventory => vec![gamepad::Button::Select],
+        Action::OpenCrafting => vec![gamepad::Button::East],
+        Action::Pause => vec![gamepad::Button::Start],
+        // items are held from the inventory screen instead, there aren't enough buttons for every slot
+        _ => Vec::new()
+      };
+      (*action, buttons)
+    }).collect();
+    Bindings { keys }
+  }
+}
+
+impl<B> Bindings<B> where B: Serialize + DeserializeOwned + Copy + PartialEq + std::fmt::Debug, Bindings<B>: Default {
+
+  pub fn from_yaml (yaml: &str) -> Result<Self, serde_yaml::Error> {
+    let mut bindings: Bindings<B> = serde_yaml::from_str(yaml)?;
+    // fill in anything the file didn't mention
+    for (action, keys) in Bindings::default().keys {
+      bindings.keys.entry(action).or_insert(keys);
+    }
+    Ok(bindings)
+  }
+
+  // read bindings from a file, using the defaults if there isn't one
+  pub fn load_or_default (path: &str) -> Self {
+    config::load_yaml_or(path, "the default controls", Bindings::from_yaml, Bindings::default)
+  }
+
+  pub fn save (&self, path: &str) -> io::Result<()> {
+    let yaml = serde_yaml::to_string(self).map_err(io::Error::other)?;
+    fs::write(path, yaml)
+  }
+
+  pub fn keys (&self, action: Action) -> &[B] {
+    self.keys.get(&action).map_or(&[], |keys| keys.as_slice())
+  }
+
+  /**
+   * Bind a key to an action, taking it off whatever it did before. The newest key goes first, and the oldest is
+   * dropped if the action already has as many keys as it can. The last key for pausing can't be taken away, since
+   * there'd be no way back to the controls screen without it. Returns whether the key was bound
+   */
+  pub fn bind (&mut self, action: Action, key: B) -> bool {
+    if action != Action::Pause && self.keys(Action::Pause) == [key] {
+      return false;
+    }
+    for keys in self.keys.values_mut() {
+      keys.retain(|bound| *bound != key);
+    }
+    let keys = self.keys.entry(action).or_default();
+    keys.insert(0, key);
+    keys.truncate(MAX_KEYS_PER_ACTION);
+    true
+  }
+
+  // the keys bound to an action written out for the controls screen
+  pub fn describe (&self, action: Action) -> String {
+    let keys: Vec<String> = self.keys(action).iter().map(|key| format!("{:?}", key)).collect();
+    if keys.is_empty() { String::from("None") } else { keys.join(" ") }
+  }
+
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn bind_moves_a_key_and_keeps_the_newest () {
+    let mut bindings: Bindings = Bindings::default();
+    assert!(bindings.bind(Action::Interact, VirtualKeyCode::Space));
+    assert!(bindings.bind(Action::Interact, VirtualKeyCode::W));
+    assert_eq!(bindings.keys(Action::Interact), [VirtualKeyCode::W, VirtualKeyCode::Space]);
+    assert_eq!(bindings.keys(Action::MoveUp), [VirtualKeyCode::Up]);
+  }
+
+  #[test]
+  fn the_last_pause_key_cant_be_taken () {
+    let mut bindings: Bindings = Bindings::default();
+    assert!(!bindings.bind(Action::Interact, VirtualKeyCode::Escape));
+    assert_eq!(bindings.keys(Action::Pause), [VirtualKeyCode::Escape]);
+    assert_eq!(bindings.keys(Action::Interact), [VirtualKeyCode::F]);
+    // once pause has another key, the old one can go
+    assert!(bindings.bind(Action::Pause, VirtualKeyCode::P));
+    assert!(bindings.bind(Action::Interact, VirtualKeyCode::Escape));
+    assert_eq!(bindings.keys(Action::Pause), [VirtualKeyCode::P]);
+  }
+
+  #[test]
+  fn missing_actions_keep_their_defaults () {
+    let bindings: Bindings = Bindings::from_yaml("Interact: [Space]\n").unwrap();
+    assert_eq!(bindings.keys(Action::Interact), [VirtualKeyCode::Space]);
+    assert_eq!(bindings.keys(Action::Pause), [VirtualKeyCode::Escape]);
+  }
+
+}
@@ -0,0 +1,336 @@
+use serde::{Serialize, Deserialize};
+use crate::tiles;
+
+// how many slots the player's inventory has
+pub const INVENTORY_SLOTS: usize = 32;
+
+/**
+ * Some amount of a single kind of item, taking up one inventory slot
+ */
+#[derive(Clone, Copy, Debug)]
+pub struct ItemStack<'a> {
+  pub item: tiles::ItemProperties<'a>,
+  pub count: u32,
+  pub durability: Option<u32> // uses left, for tools
+}
+
+impl<'a> ItemStack<'a> {
+
+  pub fn new (item: tiles::ItemProperties<'a>, count: u32) -> Self {
+    ItemStack { item, count, durability: item.tool.map(|tool| tool.durability) }
+  }
+
+  // whether another stack holds the same kind of item, and could be merged into this one
+  pub fn same_item (&self, other: &ItemStack) -> bool {
+    self.item.id == other.item.id
+  }
+
+  pub fn to_saved (&self, slot: usize) -> SavedStack {
+    SavedStack { slot, item: self.item.id, count: self.count, durability: self.durability }
+  }
+
+  // rebuild a stack from a save, if its item still exists
+  pub fn from_saved (saved: &SavedStack) -> Option<ItemStack<'static>> {
+    let item = tiles::registry().item_definition(saved.item)?;
+    let mut stack = ItemStack::new(item.properties(), saved.count);
+    if saved.durability.is_some() {
+      stack.durability = saved.durability;
+    }
+    Some(stack)
+  }
+
+  // the same stack, with its item looked up again in the registry so it can be kept anywhere
+  pub fn to_static (&self) -> Option<ItemStack<'static>> {
+    ItemStack::from_saved(&self.to_saved(0))
+  }
+
+  // how many more of this item fit in the stack
+  pub fn space (&self) -> u32 {
+    self.item.max_stack.saturating_sub(self.count)
+  }
+
+}
+
+/**
+ * An inventory slot as it's written in a save file. Items are saved by id so renaming them doesn't break saves
+ */
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SavedStack {
+  pub slot: usize,
+  pub item: u16,
+  pub count: u32,
+  #[serde(default)]
+  pub durability: Option<u32>
+}
+
+/**
+ * A fixed number of slots, each either empty or holding a stack of items. Operations that can run out of room
+ * hand back whatever didn't fit, so nothing is lost without the caller knowing
+ */
+#[derive(Clone)]
+pub struct Inventory<'a> {
+  pub slots: Vec<Option<ItemStack<'a>>>
+}
+
+impl<'a> Inventory<'a> {
+
+  pub fn new (size: usize) -> Self {
+    Inventory { slots: vec![None; size] }
+  }
+
+  // the filled slots, for saving
+  pub fn to_saved (&self) -> Vec<SavedStack> {
+    self.slots.iter().enumerate().filter_map(|(slot, stack)| stack.map(|stack| stack.to_saved(slot))).collect()
+  }
+
+  // empty out every slot, returning everything that was in them
+  pub fn take_all (&mut self) -> Vec<ItemStack<'a>> {
+    self.slots.iter_mut().filter_map(|slot| slot.take()).collect()
+  }
+
+  /**
+   * Rebuild an inventory from a save. Items that aren't in the registry anymore, or don't fit, are skipped, and
+   * the ids of what was skipped are returned with it
+   */
+  pub fn from_saved (saved: &[SavedStack], size: usize) -> (Inventory<'static>, Vec<u16>) {
+    let mut inventory = Inventory::new(size);
+    let mut skipped: Vec<u16> = Vec::new();
+    for stack in saved {
+      match ItemStack::from_saved(stack) {
+        Some(loaded) if stack.slot < size => inventory.slots[stack.slot] = Some(loaded),
+        _ => skipped.push(stack.item)
+      }
+    }
+    skipped.sort();
+    skipped.dedup();
+    (inventory, skipped)
+  }
+
+  pub fn get (&self, slot: usize) -> Option<&ItemStack<'a>> {
+    self.slots.get(slot).and_then(|stack| stack.as_ref())
+  }
+
+  // how many of an item are in the inventory altogether
+  pub fn count (&self, item_id: u16) -> u32 {
+    self.slots.iter().flatten().filter(|stack| stack.item.id == item_id).map(|stack| stack.count).sum()
+  }
+
+  // the first slot holding an item
+  pub fn find (&self, item_id: u16) -> Option<usize> {
+    self.slots.iter().position(|stack| matches!(stack, Some(stack) if stack.item.id == item_id))
+  }
+
+  pub fn is_full (&self) -> bool {
+    self.slots.iter().all(|stack| stack.is_some())
+  }
+
+  /**
+   * Add a stack of items, topping up stacks of the same item before using empty slots.
+   * Returns whatever didn't fit, or None if everything was added
+   */
+  pub fn add (&mut self, mut stack: ItemStack<'a>) -> Option<ItemStack<'a>> {
+    // fill up existing stacks first
+    for existing in self.slots.iter_mut().flatten() {
+      if stack.count == 0 {
+        break;
+      }
+      if existing.same_item(&stack) {
+        let moved = existing.space().min(stack.count);
+        existing.count += moved;
+        stack.count -= moved;
+      }
+    }
+    // then start new stacks in empty slots
+    for slot in self.slots.iter_mut().filter(|slot| slot.is_none()) {
+      if stack.count == 0 {
+        break;
+      }
+      let moved = stack.item.max_stack.min(stack.count);
+      *slot = Some(ItemStack { count: moved, ..stack });
+      stack.count -= moved;
+    }
+    if stack.count > 0 { Some(stack) } else { None }
+  }
+
+  /**
+   * Remove some of an item from wherever it is in the inventory, emptying the last stacks first.
+   * Returns how many couldn't be removed because there weren't enough
+   */
+  pub fn remove (&mut self, item_id: u16, mut count: u32) -> u32 {
+    for slot in self.slots.iter_mut().rev() {
+      if count == 0 {
+        break;
+      }
+      if let Some(stack) = slot {
+        if stack.item.id == item_id {
+          let removed = stack.count.min(count);
+          stack.count -= removed;
+          count -= removed;
+          if stack.count == 0 {
+            *slot = None;
+          }
+        }
+      }
+    }
+    count
+  }
+
+  // take up to a number of items out of a slot, returning what was taken
+  pub fn take (&mut self, slot: usize, count: u32) -> Option<ItemStack<'a>> {
+    let stack = self.slots.get_mut(slot)?.as_mut()?;
+    let taken = stack.count.min(count);
+    stack.count -= taken;
+    let taken_stack = ItemStack { count: taken, ..*stack };
+    if stack.count == 0 {
+      self.slots[slot] = None;
+    }
+    if taken > 0 { Some(taken_stack) } else { None }
+  }
+
+  /**
+   * Move some items from a slot into a new stack in the first empty slot. Returns the new slot,
+   * or None if there was no empty slot or not enough items to split off
+   */
+  pub fn split (&mut self, slot: usize, count: u32) -> Option<usize> {
+    let stack = self.get(slot)?;
+    if count == 0 || count >= stack.count {
+      return None;
+    }
+    let empty = self.slots.iter().position(|stack| stack.is_none())?;
+    let taken = self.take(slot, count)?;
+    self.slots[empty] = Some(taken);
+    Some(empty)
+  }
+
+  /**
+   * Move as much as fits from one slot onto the stack in another. If the other slot is empty the whole stack
+   * is moved, and if it holds a different item the two are swapped. Returns whatever was left behind
+   */
+  pub fn merge (&mut self, from: usize, to: usize) -> Option<ItemStack<'a>> {
+    if from == to || from >= self.slots.len() || to >= self.slots.len() {
+      return self.get(from).copied();
+    }
+    match (self.slots[from], self.slots[to]) {
+      (Some(mut source), Some(mut target)) if source.same_item(&target) => {
+        let moved = target.space().min(source.count);
+        target.count += moved;
+        source.count -= moved;
+        self.slots[to] = Some(target);
+        self.slots[from] = if source.count > 0 { Some(source) } else { None };
+      },
+      _ => self.slots.swap(from, to)
+    }
+    self.slots[from]
+  }
+
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn stack (name: &str, count: u32) -> ItemStack<'static> {
+    ItemStack::new(tiles::registry().item(name), count)
+  }
+
+  fn counts (inventory: &Inventory<'static>) -> Vec<Option<(&'static str, u32)>> {
+    inventory.slots.iter().map(|slot| slot.map(|stack| (stack.item.name, stack.count))).collect()
+  }
+
+  #[test]
+  fn add_tops_up_stacks_then_fills_empty_slots () {
+    let mut inventory = Inventory::new(3);
+    assert!(inventory.add(stack("log", 90)).is_none());
+    assert!(inventory.add(stack("stick", 1)).is_none());
+    assert!(inventory.add(stack("log", 20)).is_none());
+    assert_eq!(counts(&inventory), vec![Some(("Log", 99)), Some(("Stick", 1)), Some(("Log", 11))]);
+  }
+
+  #[test]
+  fn add_hands_back_what_doesnt_fit () {
+    let mut inventory = Inventory::new(2);
+    let leftover = inventory.add(stack("log", 250)).unwrap();
+    assert_eq!(leftover.count, 52);
+    assert_eq!(inventory.count(leftover.item.id), 198);
+    assert!(inventory.is_full());
+  }
+
+  #[test]
+  fn tools_dont_stack () {
+    let mut inventory = Inventory::new(4);
+    assert_eq!(stack("axe", 1).item.max_stack, 1);
+    assert!(inventory.add(stack("axe", 3)).is_none());
+    assert_eq!(counts(&inventory), vec![Some(("Axe", 1)), Some(("Axe", 1)), Some(("Axe", 1)), None]);
+    assert_eq!(inventory.get(0).unwrap().durability, Some(80));
+  }
+
+  #[test]
+  fn remove_empties_the_last_stacks_first_and_says_whats_missing () {
+    let mut inventory = Inventory::new(3);
+    inventory.add(stack("log", 150));
+    let log = tiles::registry().item("log").id;
+    assert_eq!(inventory.remove(log, 60), 0);
+    assert_eq!(counts(&inventory), vec![Some(("Log", 90)), None, None]);
+    assert_eq!(inventory.remove(log, 100), 10);
+    assert_eq!(inventory.count(log), 0);
+    assert!(inventory.find(log).is_none());
+  }
+
+  #[test]
+  fn split_needs_some_but_not_all_of_a_stack_and_an_empty_slot () {
+    let mut inventory = Inventory::new(2);
+    inventory.add(stack("log", 10));
+    assert_eq!(inventory.split(0, 0), None);
+    assert_eq!(inventory.split(0, 10), None);
+    assert_eq!(inventory.split(1, 1), None);
+    assert_eq!(inventory.split(0, 4), Some(1));
+    assert_eq!(counts(&inventory), vec![Some(("Log", 6)), Some(("Log", 4))]);
+    // no empty slot left to split into
+    assert_eq!(inventory.split(0, 1), None);
+    assert_eq!(counts(&inventory), vec![Some(("Log", 6)), Some(("Log", 4))]);
+  }
+
+  #[test]
+  fn merge_combines_the_same_item () {
+    let mut inventory = Inventory::new(2);
+    inventory.slots[0] = Some(stack("log", 30));
+    inventory.slots[1] = Some(stack("log", 80));
+    let left = inventory.merge(0, 1).unwrap();
+    assert_eq!(left.count, 11);
+    assert_eq!(counts(&inventory), vec![Some(("Log", 11)), Some(("Log", 99))]);
+    inventory.slots[1] = Some(stack("log", 1));
+    assert!(inventory.merge(0, 1).is_none());
+    assert_eq!(counts(&inventory), vec![None, Some(("Log", 12))]);
+  }
+
+  #[test]
+  fn merge_swaps_different_items () {
+    let mut inventory = Inventory::new(2);
+    inventory.slots[0] = Some(stack("log", 30));
+    inventory.slots[1] = Some(stack("stick", 5));
+    assert_eq!(inventory.merge(0, 1).unwrap().item.name, "Stick");
+    assert_eq!(counts(&inventory), vec![Some(("Stick", 5)), Some(("Log", 30))]);
+  }
+
+  #[test]
+  fn merge_onto_itself_does_nothing () {
+    let mut inventory = Inventory::new(2);
+    inventory.slots[0] = Some(stack("log", 30));
+    assert_eq!(inventory.merge(0, 0).unwrap().count, 30);
+    assert_eq!(counts(&inventory), vec![Some(("Log", 30)), None]);
+  }
+
+  #[test]
+  fn from_saved_skips_unknown_items () {
+    let log = tiles::registry().item("log").id;
+    let saved = vec![
+      SavedStack { slot: 0, item: log, count: 5, durability: None },
+      SavedStack { slot: 1, item: 9999, count: 1, durability: None },
+      SavedStack { slot: 40, item: log, count: 1, durability: None }
+    ];
+    let (inventory, skipped) = Inventory::from_saved(&saved, 4);
+    assert_eq!(counts(&inventory), vec![Some(("Log", 5)), None, None, None]);
+    assert_eq!(skipped, vec![log, 9999]);
+  }
+
+}
@@ -0,0 +1,20 @@
+//! The game itself, which can be played without a window. The `worldxplore` binary is a front-end that opens a
+//! window, turns keyboard and mouse input into `game::InputEvent`s and draws the game with `render`.
+
+pub mod worldgen;
+pub mod render;
+pub mod player;
+pub mod tiles;
+pub mod ui;
+pub mod world;
+pub mod inventory;
+pub mod crafting;
+pub mod rules;
+pub mod collision;
+pub mod camera;
+pub mod timestep;
+pub mod game;
+pub mod input;
+pub mod gamepad;
+pub mod save;
+pub mod config;
@@ -1,32 +1,76 @@
-mod worldgen;
-mod render;
-mod player;
-mod tiles;
-mod ui;
-
+use worldxplore::{camera, game, input, player, render, rules, tiles, timestep, worldgen};
+#[cfg(feature = "controllers")]
+use worldxplore::gamepad;
+use game::InputEvent;
 use winit::{
-  event::{Event, WindowEvent, ElementState},
+  event::{Event, WindowEvent, ElementState, KeyboardInput},
   event_loop::{ControlFlow, EventLoop},
   window::WindowBuilder,
 };
 use winit_input_helper::WinitInputHelper;
 
-// Dimensions of the world
-const WIDTH: i32 = 1000;
-const HEIGHT: i32 = 1000;
-
 // Dimensions of the camera
-const CAM_WIDTH: i32 = 33;
-const CAM_HEIGHT: i32 = 25;
+const CAM_WIDTH: i32 = camera::DEFAULT_WIDTH;
+const CAM_HEIGHT: i32 = camera::DEFAULT_HEIGHT;
+// where the keys and controller buttons for each action are kept
+const CONTROLS_PATH: &str = "controls.yaml";
+const GAMEPAD_CONTROLS_PATH: &str = "gamepad.yaml";
+
+// the controller buttons the game knows about, and what gilrs calls them
+#[cfg(feature = "controllers")]
+const GILRS_BUTTONS: [(gilrs::Button, gamepad::Button); 16] = [
+  (gilrs::Button::South, gamepad::Button::South), (gilrs::Button::East, gamepad::Button::East),
+  (gilrs::Button::North, gamepad::Button::North), (gilrs::Button::West, gamepad::Button::West),
+  (gilrs::Button::LeftTrigger, gamepad::Button::LeftBumper), (gilrs::Button::RightTrigger, gamepad::Button::RightBumper),
+  (gilrs::Button::LeftTrigger2, gamepad::Button::LeftTrigger), (gilrs::Button::RightTrigger2, gamepad::Button::RightTrigger),
+  (gilrs::Button::Select, gamepad::Button::Select), (gilrs::Button::Start, gamepad::Button::Start),
+  (gilrs::Button::LeftThumb, gamepad::Button::LeftStick), (gilrs::Button::RightThumb, gamepad::Button::RightStick),
+  (gilrs::Button::DPadUp, gamepad::Button::DPadUp), (gilrs::Button::DPadDown, gamepad::Button::DPadDown),
+  (gilrs::Button::DPadLeft, gamepad::Button::DPadLeft), (gilrs::Button::DPadRight, gamepad::Button::DPadRight)
+];
+
+/**
+ * Real controllers, read with gilrs. Only the first one that's plugged in is used
+ */
+#[cfg(feature = "controllers")]
+struct GilrsGamepad {
+  gilrs: gilrs::Gilrs
+}
+
+#[cfg(feature = "controllers")]
+impl gamepad::Gamepad for GilrsGamepad {
+  fn state (&mut self) -> Option<gamepad::GamepadState> {
+    // gilrs only keeps its controllers up to date as their events are taken out
+    while self.gilrs.next_event().is_some() {}
+    let (_, pad) = self.gilrs.gamepads().next()?;
+    let buttons = GILRS_BUTTONS.iter().filter(|(gilrs_button, _)| pad.is_pressed(*gilrs_button)).map(|(_, button)| *button).collect();
+    // up is positive for gilrs, but negative in the world
+    Some(gamepad::GamepadState { buttons, stick_x: pad.value(gilrs::Axis::LeftStickX), stick_y: -pad.value(gilrs::Axis::LeftStickY) })
+  }
+}
 
 async fn run() {
   #[cfg(target_arch = "wasm32")]
   console_error_panic_hook::set_once();
+  // load tile and item definitions, from content.yaml if it exists
+  tiles::init_registry(tiles::Registry::load_or_builtin("content.yaml"));
   // create input manager
   let mut input = WinitInputHelper::new();
   // generate the world
   let seed = rand::random::<u32>();
-  let mut world = worldgen::elevation_to_tiles(worldgen::generate_perlin(WIDTH, HEIGHT, seed));
+  println!("Seed: {}", seed);
+  // a preset can be picked by name on the command line, otherwise settings come from worldgen.yaml if it exists
+  let settings = match std::env::args().nth(1) {
+    Some(name) => worldgen::WorldGenSettings::preset(&name).unwrap_or_else(|| {
+      println!("Unknown world preset {}, using the default", name);
+      worldgen::WorldGenSettings::default()
+    }),
+    None => worldgen::WorldGenSettings::load_or_default("worldgen.yaml")
+  };
+  // rules for things like what happens on death come from rules.yaml if it exists
+  let mut game = game::Game::new(seed, settings, rules::GameRules::load_or_default("rules.yaml"));
+  // a name that isn't taken yet, so saving doesn't replace a world that's already there
+  game.player.world_name = game.player.saves.unused_name(player::DEFAULT_WORLD_NAME);
   // create a window
   let event_loop = EventLoop::new();
   let window = WindowBuilder::new().with_title("WorldXPlore Alpha").build(&event_loop).unwrap();
@@ -44,10 +88,31 @@ async fn run() {
       })
       .expect("couldn't append canvas to document body");
   }
-  // create player state
-  let mut player = player::Player::new(CAM_WIDTH, CAM_HEIGHT, seed);
+  // how the camera follows the player comes from camera.yaml if it exists
+  game.player.camera.settings = camera::CameraSettings::load_or_default("camera.yaml");
+  // which keys do what comes from controls.yaml if it exists, and is saved back there when it's changed
+  game.player.bindings = input::Bindings::load_or_default(CONTROLS_PATH);
+  game.player.gamepad_bindings = input::Bindings::load_or_default(GAMEPAD_CONTROLS_PATH);
+  let mut saved_bindings = game.player.bindings.clone();
+  let mut saved_gamepad_bindings = game.player.gamepad_bindings.clone();
+  // the game can still be played with the keyboard if controllers can't be read
+  #[cfg(feature = "controllers")]
+  let mut controller = match gilrs::Gilrs::new() {
+    Ok(gilrs) => Some(GilrsGamepad { gilrs }),
+    Err(err) => {
+      println!("Couldn't read controllers: {}", err);
+      None
+    }
+  };
   // create renderer
-  let mut renderer = render::Render::new(&window, &mut world, &mut player, CAM_WIDTH, CAM_HEIGHT).await;
+  let mut renderer = render::Render::new(&window, &mut game.world, &mut game.player, CAM_WIDTH, CAM_HEIGHT).await;
+
+  // the game is updated a fixed number of times a second, separately from drawing
+  let mut timestep = timestep::Timestep::new();
+  // the last key pressed, for binding to an action on the controls screen
+  let mut last_key: Option<winit::event::VirtualKeyCode> = None;
+  // characters typed since the last update, for naming worlds
+  let mut typed: Vec<char> = Vec::new();
 
   // run event loop
   event_loop.run(move | event, _, control_flow | {
@@ -60,38 +125,85 @@ async fn run() {
         WindowEvent::ScaleFactorChanged { new_inner_size, .. } => {
           renderer.resize(**new_inner_size);
         },
+        WindowEvent::KeyboardInput { input: KeyboardInput { virtual_keycode: Some(key), state: ElementState::Pressed, .. }, .. } => {
+          last_key = Some(*key);
+        },
+        WindowEvent::ReceivedCharacter(character) => {
+          typed.push(*character);
+        },
         _ => ()
       }
       _ => ()
     }
     if input.update(&event) {
 
-      if input.key_released(winit::event::VirtualKeyCode::Escape) {
-        player.pause_type = player::PauseType::Pause;
-        player.paused = !player.paused;
-      } else if input.key_released(winit::event::VirtualKeyCode::E) {
-        player.pause_type = player::PauseType::Inventory;
-        player.paused = !player.paused;
-      }
       if input.close_requested() {
         *control_flow = ControlFlow::Exit;
       }
 
-      if input.key_pressed(winit::event::VirtualKeyCode::W) { player.key_pressed(winit::event::VirtualKeyCode::W); }
-      if input.key_pressed(winit::event::VirtualKeyCode::A) { player.key_pressed(winit::event::VirtualKeyCode::A); }
-      if input.key_pressed(winit::event::VirtualKeyCode::S) { player.key_pressed(winit::event::VirtualKeyCode::S); }
-      if input.key_pressed(winit::event::VirtualKeyCode::D) { player.key_pressed(winit::event::VirtualKeyCode::D); }
-      if input.key_released(winit::event::VirtualKeyCode::W) { player.key_released(winit::event::VirtualKeyCode::W); }
-      if input.key_released(winit::event::VirtualKeyCode::A) { player.key_released(winit::event::VirtualKeyCode::A); }
-      if input.key_released(winit::event::VirtualKeyCode::S) { player.key_released(winit::event::VirtualKeyCode::S); }
-      if input.key_released(winit::event::VirtualKeyCode::D) { player.key_released(winit::event::VirtualKeyCode::D); }
-
       let (mouse_x, mouse_y) = input.mouse().unwrap_or((0., 0.));
 
       // adjust mouse position to the same coordinate system as WGPU
       let relative_mouse_pos: [f32; 2] = [(mouse_x / window.inner_size().width as f32 - 0.5) * 2., (1. - mouse_y / window.inner_size().height as f32 - 0.5) * 2. ]; 
 
-      renderer.update(&mut world, &mut player, relative_mouse_pos, input.mouse_pressed(0), control_flow);
+      // turn keys into actions with the player's bindings, and clicks into things for the game to do
+      let mut events: Vec<InputEvent> = Vec::new();
+      let rebinding = game.player.rebinding;
+      // while a world's name is being typed, keys type instead of doing their actions
+      let typing = game.player.world_list.renaming.is_some();
+      for (action, keys) in game.player.bindings.keys.iter() {
+        // an action is only let go of once none of its keys are held
+        if keys.iter().any(|key| input.key_pressed(*key)) && rebinding.is_none() && !typing {
+          events.push(InputEvent::ActionPressed(*action));
+        } else if keys.iter().any(|key| input.key_released(*key)) && !keys.iter().any(|key| input.key_held(*key)) {
+          events.push(InputEvent::ActionReleased(*action));
+        }
+      }
+      // while the controls screen is waiting for a key, the next one pressed is bound instead of doing anything
+      if let (Some(action), Some(key)) = (rebinding, last_key) {
+        events.push(InputEvent::Bind(action, key));
+      }
+      last_key = None;
+      if typing {
+        events.extend(typed.iter().map(|character| InputEvent::Type(*character)));
+      }
+      typed.clear();
+
+      // harvest (or build on) a tile by clicking on it
+      if input.mouse_pressed(0) && !game.player.paused {
+        let (tile_x, tile_y) = game.player.mouse_tile(relative_mouse_pos);
+        events.push(InputEvent::Interact(tile_x, tile_y));
+      }
+
+      for event in events {
+        if game.handle(event) {
+          renderer.force_update = true;
+        }
+      }
+      #[cfg(feature = "controllers")]
+      if let Some(controller) = &mut controller {
+        if game.poll_gamepad(controller) {
+          renderer.force_update = true;
+        }
+      }
+      if game.player.bindings != saved_bindings {
+        if let Err(err) = game.player.bindings.save(CONTROLS_PATH) {
+          println!("Couldn't save the controls to {}: {}", CONTROLS_PATH, err);
+        }
+        saved_bindings = game.player.bindings.clone();
+      }
+      if game.player.gamepad_bindings != saved_gamepad_bindings {
+        if let Err(err) = game.player.gamepad_bindings.save(GAMEPAD_CONTROLS_PATH) {
+          println!("Couldn't save the controller controls to {}: {}", GAMEPAD_CONTROLS_PATH, err);
+        }
+        saved_gamepad_bindings = game.player.gamepad_bindings.clone();
+      }
+
+      // run however many ticks are due, then draw part of the way to the next one
+      for _ in 0..timestep.advance() {
+        game.tick();
+      }
+      renderer.update(&mut game.world, &mut game.player, timestep.alpha(), relative_mouse_pos, input.mouse_pressed(0), control_flow);
       let _ = renderer.render();
 
     }
@@ -1,99 +1,309 @@
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashSet};
 use std::convert::TryInto;
-use std::fs;
 use bytemuck::{Pod, Zeroable};
 use serde::{Serialize, Deserialize};
+use crate::camera;
+use crate::collision;
+use crate::crafting;
+use crate::gamepad;
+use crate::input;
+use crate::inventory;
 use crate::tiles;
 use crate::render; // for the tileset size constants
+use crate::rules;
+use crate::save;
 use crate::ui;
+use crate::ui::GenVertices;
+use crate::timestep;
+use crate::world;
 use crate::worldgen;
 
 use std::f32::consts::PI;
 
+// how many tiles away from their feet the player can reach to interact with things
+pub const INTERACT_REACH: i32 = 2;
+// how many updates the player can't be hurt again for after taking damage
+pub const INVULNERABLE_TICKS: u32 = 60;
+// how many updates a message like a tool breaking stays on screen for
+pub const MESSAGE_TICKS: u32 = 180;
+// how fast the player gets pushed away from something that hurt them
+pub const KNOCKBACK_SPEED: f32 = 0.15;
+// how far from a campfire to look for somewhere to respawn
+pub const RESPAWN_SEARCH_DISTANCE: i32 = 4;
+// how full the hunger, thirst and stamina meters can get
+pub const MAX_METER: f32 = 10.;
+// how many seconds a whole day and night takes
+pub const DAY_LENGTH: f32 = 60.;
+// how much faster the player speeds up while sprinting
+pub const SPRINT_FACTOR: f32 = 1.6;
+// the size of the box around the player's feet that bumps into things, as half of its width and height in tiles
+pub const FEET_HALF_WIDTH: f32 = 0.3;
+pub const FEET_HALF_HEIGHT: f32 = 0.2;
+// how many tiles tall the player's sprite is. the bottom of it lines up with the bottom of their feet
+pub const SPRITE_HEIGHT: f32 = 3.;
+// what a new world is called, before a number is added to keep it from replacing another world
+pub const DEFAULT_WORLD_NAME: &str = "New Game";
+// the longest name that can be typed for a world
+pub const MAX_WORLD_NAME_LENGTH: usize = 24;
+// how many worlds fit on one page of the worlds screen
+pub const WORLDS_PER_PAGE: usize = 8;
+
 pub struct Player<'a> {
-  pub keys_down: HashSet<winit::event::VirtualKeyCode>,
-  pub width: i32,
-  pub height: i32,
-  pub x: f32,
+  pub actions_held: HashSet<input::Action>, // actions whose keys are being held down, like moving and sprinting
+  pub stick: (f32, f32), // how far a controller's stick is pushed, past its dead zone, from -1 to 1 on each axis
+  pub x: f32, // the middle of the player's feet, in world coordinates
   pub y: f32,
+  pub prev_x: f32, // where the player was on the tick before, for drawing between ticks
+  pub prev_y: f32,
   pub x_speed: f32,
   pub y_speed: f32,
+  pub facing: (i32, i32), // the direction the player last moved in, as a tile offset
+  pub camera: camera::Camera,
   pub uniforms: Uniforms,
   pub is_swimming: bool,
   pub health: f32,
   pub max_health: f32,
+  // how full the player is of food, water and energy, each from 0 to MAX_METER
+  pub hunger: f32,
+  pub thirst: f32,
+  pub stamina: f32,
+  pub sprinting: bool,
+  pub invulnerable_ticks: u32, // counts down after being hurt, and the player can't be hurt again until it's 0
+  pub last_hurt_by: String, // what last hurt the player, to say what killed them
+  pub dead: bool,
+  pub death_stats: DeathStats,
+  pub spawn_point: (i32, i32), // where the player first appeared in the world
+  pub respawn_point: Option<(i32, i32)>, // the last campfire the player placed, if they've placed one
+  pub rules: rules::GameRules,
+  pub time: f32, // how many seconds the game has been played for, which decides the time of day
   pub paused: bool,
   pub pause_type: PauseType,
   pub seed: u32,
-  pub world_name: String,
-  pub inventory: Vec<tiles::ItemProperties<'a>>
+  pub world_name: String, // which save in the save index this world is
+  pub saves: save::SaveIndex,
+  pub world_list: WorldList,
+  pub inventory: inventory::Inventory<'a>,
+  pub selected: Option<usize>, // the inventory slot of the item being held
+  pub building: bool, // whether interacting places the selected item instead of harvesting
+  pub message: String, // something that just happened while playing, like a tool breaking
+  pub message_ticks: u32, // counts down, and the message is hidden once it's 0
+  pub crafting_message: String, // what happened the last time something was crafted
+  pub save_message: String, // what happened the last time the game was saved or loaded
+  pub bindings: input::Bindings,
+  pub gamepad_bindings: input::Bindings<gamepad::Button>,
+  pub rebinding: Option<input::Action>, // the action waiting for a key on the controls screen
+  pub focus: ui::Focus // the button picked in a menu without the mouse
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+/**
+ * Everything in a save, in the current version of the format. Older saves are upgraded to this by the save module
+ * before they're read as one
+ */
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct GameData {
-  pub x: f32,
-  pub y: f32,
+  pub version: u32,
+  pub position: (f32, f32), // the middle of the player's feet
   pub health: f32,
   pub max_health: f32,
   pub seed: u32,
-  pub time: f32
+  pub time: f32, // in seconds
+  // older saves won't have settings, and were all made with the defaults
+  #[serde(default)]
+  pub settings: worldgen::WorldGenSettings,
+  // everything that's been harvested or built, since the seed can't regenerate it
+  #[serde(default)]
+  pub chunks: Vec<world::ChunkChanges>,
+  // the tile a handmade flat world is made of, if it isn't generated from the seed
+  #[serde(default)]
+  pub flat: Option<u16>,
+  // older saves didn't keep the inventory, so they start with a new one
+  #[serde(default)]
+  pub inventory: Option<Vec<inventory::SavedStack>>,
+  #[serde(default)]
+  pub piles: Vec<world::SavedPile>,
+  #[serde(default)]
+  pub rules: rules::GameRules,
+  #[serde(default)]
+  pub death_stats: DeathStats,
+  // older saves will look for the spawn point again
+  #[serde(default)]
+  pub spawn_point: Option<(i32, i32)>,
+  #[serde(default)]
+  pub respawn_point: Option<(i32, i32)>,
+  // older saves start with full meters
+  #[serde(default = "full_meter")]
+  pub hunger: f32,
+  #[serde(default = "full_meter")]
+  pub thirst: f32,
+  #[serde(default = "full_meter")]
+  pub stamina: f32
+}
+
+fn full_meter () -> f32 { MAX_METER }
+
+/**
+ * Everything worth remembering about the player's deaths
+ */
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct DeathStats {
+  pub deaths: u32,
+  pub last_position: Option<(i32, i32)>,
+  pub last_cause: Option<String>,
+  pub causes: BTreeMap<String, u32> // how many times each thing has killed the player
 }
 
 /**
  * The various ways the game can be paused.
  */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum PauseType {
   Pause,
-  Inventory
+  Inventory,
+  Crafting,
+  Controls,
+  Worlds,
+  Dead
+}
+
+/**
+ * What's happening on the worlds screen
+ */
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct WorldList {
+  pub worlds: Vec<save::WorldInfo>, // the saved worlds, as of the last time the list was opened or changed
+  pub selected: Option<usize>,
+  pub page: usize,
+  pub renaming: Option<String>, // the new name being typed for the selected world
+  pub confirm_delete: bool // delete has been clicked once, and has to be clicked again to really delete
+}
+
+/**
+ * The buttons on the worlds screen
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WorldButton {
+  Select(usize),
+  PreviousPage,
+  NextPage,
+  Load,
+  Rename,
+  Duplicate,
+  Delete,
+  New,
+  Back
 }
 
 impl Player<'_> {
 
   pub fn new (width: i32, height: i32, seed: u32) -> Self {
     Player {
-      keys_down: HashSet::new(),
-      x: 0., y: 0., x_speed: 0., y_speed: 0.,
-      width, height, seed,
+      actions_held: HashSet::new(), stick: (0., 0.),
+      x: 0., y: 0., prev_x: 0., prev_y: 0., x_speed: 0., y_speed: 0., facing: (0, 1),
+      camera: camera::Camera::new(width, height),
+      seed,
       uniforms: Uniforms::default(),
       is_swimming: false,
-      health: 6., max_health: 6.,
+      health: 6., max_health: 6., invulnerable_ticks: 0, last_hurt_by: String::new(),
+      hunger: MAX_METER, thirst: MAX_METER, stamina: MAX_METER, sprinting: false,
+      dead: false, death_stats: DeathStats::default(),
+      spawn_point: (0, 0), respawn_point: None,
+      rules: rules::GameRules::default(),
+      time: 0.,
       paused: false,
       pause_type: PauseType::Pause,
-      world_name: String::from("New Game"),
-      inventory: vec![tiles::SWORD, tiles::STICK]
+      world_name: String::from(DEFAULT_WORLD_NAME),
+      saves: save::SaveIndex::default(),
+      world_list: WorldList::default(),
+      inventory: starting_inventory(),
+      selected: None,
+      building: false,
+      message: String::new(), message_ticks: 0,
+      crafting_message: String::new(),
+      save_message: String::new(),
+      bindings: input::Bindings::default(),
+      gamepad_bindings: input::Bindings::default(),
+      rebinding: None,
+      focus: ui::Focus::default()
     }
   }
 
-  // if keys are pressed, update x and y values
-  pub fn update (&mut self, world: &mut Vec<Vec<tiles::TileProperties>>, cam_width: i32, cam_height: i32) {
+  // put the player's feet in the middle of a tile, bringing the camera straight there with them
+  pub fn move_to_tile (&mut self, tile_x: i32, tile_y: i32) {
+    self.x = tile_x as f32 + 0.5;
+    self.y = tile_y as f32 + 0.5;
+    self.prev_x = self.x;
+    self.prev_y = self.y;
+    self.x_speed = 0.;
+    self.y_speed = 0.;
+    let (camera_x, camera_y) = self.camera_target();
+    self.camera.snap_to(camera_x, camera_y);
+  }
+
+  // what the camera keeps in view, which is the middle of the player's sprite rather than their feet
+  pub fn camera_target (&self) -> (f32, f32) {
+    (self.x, self.y + FEET_HALF_HEIGHT - SPRITE_HEIGHT / 2.)
+  }
+
+  // move the player to a safe place to start, away from water and obstacles
+  pub fn spawn (&mut self, world: &mut world::World) {
+    let (spawn_x, spawn_y) = worldgen::find_spawn(world);
+    self.spawn_point = (spawn_x, spawn_y);
+    self.move_to_tile(spawn_x, spawn_y);
+    // the search can load a lot of chunks if it had to look far
+    world.unload_far_chunks(spawn_x, spawn_y);
+  }
 
-    self.width = cam_width;
-    self.height = cam_height;
-    
-    // only update the player position if the game is paused
-    if !self.paused {
-      self.move_character(world);
-      self.uniforms.time += 0.01;
+  /**
+   * Move the game on by one tick, which happens TICKS_PER_SECOND times a second however fast the game is drawn.
+   * Nothing happens while the game is paused
+   */
+  pub fn tick (&mut self, world: &mut world::World) {
+    if self.paused {
+      return;
+    }
+    self.prev_x = self.x;
+    self.prev_y = self.y;
+    self.move_character(world);
+    self.update_meters();
+    self.time += 1. / timestep::TICKS_PER_SECOND as f32;
+    self.message_ticks = self.message_ticks.saturating_sub(1);
+    // chunks far from the player don't need to stay in memory
+    world.unload_far_chunks(self.x.floor() as i32, self.y.floor() as i32);
+    if self.health <= 0. {
+      self.die(world);
     }
+    let (camera_x, camera_y) = self.camera_target();
+    self.camera.follow(camera_x, camera_y);
+  }
+
+  /**
+   * Get ready to draw a frame. Alpha is how far it is between the last tick and the next one, from 0 to 1,
+   * and the player and camera are drawn that far between where they were and where they are
+   */
+  pub fn update (&mut self, cam_width: i32, cam_height: i32, alpha: f32) {
+
+    // resizing only changes how much can be seen, the player stays where they are
+    self.camera.resize(cam_width, cam_height);
+    let view = self.camera.interpolated(alpha);
 
-    // make transforms for how much to offset tiles for smoother scrolling
-    let x_offset = self.x as f32 % 1.;
-    let y_offset = self.y as f32 % 1.;
-    // coordinate space is -1.0 to 1.0, so size should be double as big as 1 / size
-    let tile_width = 2. / self.width as f32;
-    let tile_height = 2. / self.height as f32;
+    // the player isn't always in the middle of the screen, since the camera can lag behind them
+    let (sprite_x, sprite_y) = self.camera_target();
+    let (sprite_x, sprite_y) = (sprite_x - (self.x - self.prev_x) * (1. - alpha), sprite_y - (self.y - self.prev_y) * (1. - alpha));
     // set as uniforms to be rendered with
     self.uniforms = Uniforms { 
-      translate_vector: [ x_offset * tile_width, y_offset * tile_height ], 
+      translate_vector: view.tile_offset(),
       is_swimming: self.is_swimming.into(),
-      time: self.uniforms.time,
-      light_intensity: light_intensity(self.uniforms.time),
-      unused: 0.
+      time: self.time,
+      light_intensity: light_intensity(self.time),
+      unused: 0.,
+      player_offset: view.to_screen(sprite_x, sprite_y),
+      padding: [0., 0.]
     };
 
   }
 
-  pub fn move_character (&mut self, world: &Vec<Vec<tiles::TileProperties>>) {
+  pub fn move_character (&mut self, world: &mut world::World) {
     // movement speed stuff
     if self.is_swimming {
       self.x_speed /= 1.8;
@@ -103,28 +313,34 @@ impl Player<'_> {
       self.y_speed /= 1.2;
     }
 
-    if self.keys_down.contains(&winit::event::VirtualKeyCode::A) {
-      self.x_speed -= 0.02;
-    }
-    if self.keys_down.contains(&winit::event::VirtualKeyCode::D) {
-      self.x_speed += 0.02;
-    }
-    if self.keys_down.contains(&winit::event::VirtualKeyCode::S) {
-      self.y_speed += 0.02;
+    // sprinting needs stamina, and can't be done while swimming
+    // the stick moves as far as it's pushed, and keys push it all the way
+    let (mut move_x, mut move_y) = self.stick;
+    for (x, y) in self.actions_held.iter().filter_map(|action| action.direction()) {
+      move_x += x as f32;
+      move_y += y as f32;
     }
-    if self.keys_down.contains(&winit::event::VirtualKeyCode::W) {
-      self.y_speed -= 0.02;
+    let (move_x, move_y) = (move_x.clamp(-1., 1.), move_y.clamp(-1., 1.));
+    let moving = move_x != 0. || move_y != 0.;
+    self.sprinting = moving && !self.is_swimming && self.stamina > 0. && self.actions_held.contains(&input::Action::Sprint);
+    // slowing tiles make it harder to speed up
+    let (feet_x, feet_y) = self.feet_tile();
+    let mut acceleration = 0.02 * world.get_tile(feet_x, feet_y).speed_factor;
+    if self.sprinting {
+      acceleration *= SPRINT_FACTOR;
     }
-    // attempt to move
-    self.x += self.x_speed;
-    // check if you can actually move to the position
-    if world[(self.x + 0.5).floor() as usize + (self.width / 2) as usize][self.y.floor() as usize + (self.height / 2) as usize + 2].solid {
-      self.x -= self.x_speed;
+    self.x_speed += acceleration * move_x;
+    self.y_speed += acceleration * move_y;
+    // move as far as we can before bumping into something, sliding along it if we hit it at an angle
+    let (moved_x, moved_y) = collision::sweep(world, &self.feet_box(), self.x_speed, self.y_speed);
+    self.x += moved_x;
+    self.y += moved_y;
+    // running into something stops us, instead of building up speed against it
+    if moved_x.abs() < self.x_speed.abs() {
+      self.x_speed = 0.;
     }
-    // same thing as above
-    self.y += self.y_speed;
-    if world[(self.x + 0.5).floor() as usize + (self.width / 2) as usize][self.y.floor() as usize + (self.height / 2) as usize + 2].solid {
-      self.y -= self.y_speed;
+    if moved_y.abs() < self.y_speed.abs() {
+      self.y_speed = 0.;
     }
 
     // set in water to false before checking if we are in water
@@ -132,36 +348,327 @@ impl Player<'_> {
     // if move lands us in water, set in water to true
     // adjustments are made to set the comparison point at the centre of the character's feet instead of
     // the top left of the screen
-    // values <= 1 are water varities
-    if world[(self.x + 0.5).floor() as usize + (self.width / 2) as usize][self.y.floor() as usize + (self.height / 2) as usize + 2].swimmable {
+    let (feet_x, feet_y) = self.feet_tile();
+    if world.get_tile(feet_x, feet_y).swimmable {
       self.is_swimming = true;
     }
 
-    // prevent from raising or lowering the x or y past bounds
-    if self.x < 0. {
-      self.x = 0.;
+    self.touch_damaging_tiles(world);
+  }
+
+  /**
+   * Hurt the player if they're standing on or right next to something damaging, like a cactus, and push
+   * them away from it. After being hurt there's a short window where it can't happen again
+   */
+  pub fn touch_damaging_tiles (&mut self, world: &mut world::World) {
+    if self.invulnerable_ticks > 0 {
+      self.invulnerable_ticks -= 1;
+      return;
+    }
+    let (feet_x, feet_y) = self.feet_tile();
+    for (offset_x, offset_y) in [(0, 0), (1, 0), (-1, 0), (0, 1), (0, -1)] {
+      let tile = world.get_tile(feet_x + offset_x, feet_y + offset_y);
+      if tile.damaging {
+        let name = tiles::registry().tile_definition(tile.id).map_or(String::new(), |tile| tile.display_name.clone());
+        self.hurt(tile.contact_damage, &name);
+        // get pushed away from the tile, or back the way we came if we're standing on it
+        let (away_x, away_y) = if (offset_x, offset_y) == (0, 0) { (-self.facing.0, -self.facing.1) } else { (-offset_x, -offset_y) };
+        self.x_speed = away_x as f32 * KNOCKBACK_SPEED;
+        self.y_speed = away_y as f32 * KNOCKBACK_SPEED;
+        return;
+      }
+    }
+  }
+
+  /**
+   * Empty the hunger, thirst and stamina meters by however much one tick takes, and take away health
+   * while hunger or thirst is empty, or while swimming with no stamina left
+   */
+  pub fn update_meters (&mut self) {
+    let hunger_rate = if self.sprinting { self.rules.hunger_rate + self.rules.sprint_hunger_rate } else { self.rules.hunger_rate };
+    self.hunger = (self.hunger - hunger_rate / timestep::TICKS_PER_SECOND as f32).max(0.);
+    self.thirst = (self.thirst - self.rules.thirst_rate / timestep::TICKS_PER_SECOND as f32).max(0.);
+    let stamina_change = if self.sprinting {
+      -self.rules.sprint_stamina_rate
+    } else if self.is_swimming {
+      -self.rules.swim_stamina_rate
+    } else {
+      self.rules.stamina_recovery_rate
+    };
+    self.stamina = (self.stamina + stamina_change / timestep::TICKS_PER_SECOND as f32).clamp(0., MAX_METER);
+
+    let cause = if self.hunger <= 0. {
+      "Hunger"
+    } else if self.thirst <= 0. {
+      "Thirst"
+    } else if self.is_swimming && self.stamina <= 0. {
+      "Drowning"
+    } else {
+      return;
+    };
+    self.health = (self.health - self.rules.empty_meter_damage / timestep::TICKS_PER_SECOND as f32).max(0.);
+    self.last_hurt_by = String::from(cause);
+  }
+
+  // eat the held item if it's food, returning whether anything was eaten
+  pub fn eat (&mut self) -> bool {
+    let slot = match self.selected {
+      Some(slot) => slot,
+      None => return false
+    };
+    let (item_id, food) = match self.inventory.get(slot).and_then(|stack| stack.item.food.map(|food| (stack.item.id, food))) {
+      Some(food) => food,
+      None => return false
+    };
+    self.hunger = (self.hunger + food.hunger).min(MAX_METER);
+    self.thirst = (self.thirst + food.thirst).min(MAX_METER);
+    self.inventory.take(slot, 1);
+    // keep holding the same food if there's more of it somewhere else
+    if self.inventory.get(slot).is_none() {
+      self.selected = self.inventory.find(item_id);
+    }
+    true
+  }
+
+  // take away some health, and start the window where the player can't be hurt again
+  pub fn hurt (&mut self, damage: f32, cause: &str) {
+    self.health = (self.health - damage).max(0.);
+    self.invulnerable_ticks = INVULNERABLE_TICKS;
+    self.last_hurt_by = String::from(cause);
+  }
+
+  /**
+   * Stop the game and show the death screen, dealing with the inventory however the rules say to
+   */
+  pub fn die (&mut self, world: &mut world::World) {
+    let (feet_x, feet_y) = self.feet_tile();
+    self.dead = true;
+    self.paused = true;
+    self.pause_type = PauseType::Dead;
+    self.building = false;
+    self.actions_held.clear();
+    self.stick = (0., 0.);
+    self.sprinting = false;
+    self.x_speed = 0.;
+    self.y_speed = 0.;
+
+    self.death_stats.deaths += 1;
+    self.death_stats.last_position = Some((feet_x, feet_y));
+    self.death_stats.last_cause = Some(self.last_hurt_by.clone());
+    *self.death_stats.causes.entry(self.last_hurt_by.clone()).or_insert(0) += 1;
+
+    match self.rules.death_penalty {
+      rules::DeathPenalty::KeepInventory => (),
+      rules::DeathPenalty::DropInventory => {
+        let items = self.inventory.take_all().iter().filter_map(|stack| stack.to_static()).collect();
+        world.drop_pile(feet_x, feet_y, items);
+      },
+      rules::DeathPenalty::LoseInventory => { self.inventory.take_all(); }
     }
-    if self.y < 0. {
-      self.y = 0.;
+    self.selected = None;
+  }
+
+  // bring the player back at their last campfire if it's still there, otherwise where they first spawned
+  pub fn respawn (&mut self, world: &mut world::World) {
+    let campfire = self.respawn_point.filter(|(x, y)| {
+      tiles::registry().tile_definition(world.get_tile_id(*x, *y)).is_some_and(|tile| tile.respawn_point)
+    });
+    if campfire.is_none() {
+      self.respawn_point = None;
     }
+    let (respawn_x, respawn_y) = campfire
+      .and_then(|(x, y)| worldgen::find_safe_near(world, x, y, RESPAWN_SEARCH_DISTANCE))
+      .unwrap_or(self.spawn_point);
+    self.move_to_tile(respawn_x, respawn_y);
+    // never less than half a heart, even if the rules or the save say so
+    self.health = (self.max_health * self.rules.respawn_health).max(1.).min(self.max_health.max(1.));
+    self.hunger = MAX_METER;
+    self.thirst = MAX_METER;
+    self.stamina = MAX_METER;
+    self.invulnerable_ticks = INVULNERABLE_TICKS;
+    self.dead = false;
+    self.paused = false;
+    self.pause_type = PauseType::Pause;
+  }
+
+  // the box around the player's feet, in world coordinates
+  pub fn feet_box (&self) -> collision::Aabb {
+    collision::Aabb::around(self.x, self.y, FEET_HALF_WIDTH, FEET_HALF_HEIGHT)
+  }
+
+  // the tile the centre of the player's feet is on
+  pub fn feet_tile (&self) -> (i32, i32) {
+    (self.x.floor() as i32, self.y.floor() as i32)
+  }
+
+  // the tile right in front of the player
+  pub fn target_tile (&self) -> (i32, i32) {
+    let (feet_x, feet_y) = self.feet_tile();
+    (feet_x + self.facing.0, feet_y + self.facing.1)
+  }
+
+  // the tile under the mouse, from a mouse position in the -1.0 to 1.0 coordinate space
+  pub fn mouse_tile (&self, mouse_pos: [f32; 2]) -> (i32, i32) {
+    let (mouse_x, mouse_y) = self.camera.to_world(mouse_pos);
+    (mouse_x.floor() as i32, mouse_y.floor() as i32)
   }
 
-  // key pressed, add it to keys down
-  pub fn key_pressed (&mut self, key: winit::event::VirtualKeyCode) {
-    self.keys_down.insert(key);
+  /**
+   * Harvest a tile if it's in reach, putting whatever it drops into the inventory, or place the selected
+   * item on it when building. Returns whether the tile changed, so the renderer knows to update
+   */
+  pub fn interact (&mut self, world: &mut world::World, tile_x: i32, tile_y: i32) -> bool {
+    let (feet_x, feet_y) = self.feet_tile();
+    if (tile_x - feet_x).abs() > INTERACT_REACH || (tile_y - feet_y).abs() > INTERACT_REACH {
+      return false;
+    }
+    // items left on the ground get picked up first, and whatever doesn't fit stays there
+    if let Some(items) = world.pick_up_pile(tile_x, tile_y) {
+      let leftovers = items.into_iter().filter_map(|stack| self.inventory.add(stack)).filter_map(|stack| stack.to_static()).collect();
+      world.drop_pile(tile_x, tile_y, leftovers);
+      return true;
+    }
+    if self.building {
+      return self.place(world, tile_x, tile_y);
+    }
+    // drinking from a river or lake doesn't change it
+    if tiles::registry().tile_definition(world.get_tile_id(tile_x, tile_y)).is_some_and(|tile| tile.fresh_water) {
+      self.thirst = (self.thirst + self.rules.drink_amount).min(MAX_METER);
+      return false;
+    }
+    match world.hit(tile_x, tile_y, self.held_tool()) {
+      world::HitResult::Harvested(drops) => {
+        self.wear_tool();
+        // whatever doesn't fit is left in a pile where it was harvested
+        let leftovers = drops.into_iter().filter_map(|drop| self.inventory.add(drop)).filter_map(|stack| stack.to_static()).collect();
+        world.drop_pile(tile_x, tile_y, leftovers);
+        true
+      },
+      world::HitResult::Damaged => {
+        self.wear_tool();
+        true
+      },
+      world::HitResult::Nothing => false
+    }
   }
-  // key released, add it to keys down
-  pub fn key_released (&mut self, key: winit::event::VirtualKeyCode) {
-    self.keys_down.remove(&key);
+
+  // the tool or weapon in the held slot, if it is one
+  pub fn held_tool (&self) -> Option<tiles::ToolDefinition> {
+    self.selected.and_then(|slot| self.inventory.get(slot)).and_then(|stack| stack.item.tool)
   }
 
-  pub fn gen_ui_vertices (&mut self, mouse_pos: [f32; 2], mouse_down: bool, control_flow: &mut winit::event_loop::ControlFlow, mut world: &mut Vec<Vec<tiles::TileProperties>>) -> (Vec<render::Vertex>, Vec<u16>) {
+  // use up some of the held tool's durability, breaking it when there's none left
+  pub fn wear_tool (&mut self) {
+    let slot = match self.selected {
+      Some(slot) => slot,
+      None => return
+    };
+    if let Some(stack) = self.inventory.slots.get_mut(slot).and_then(|stack| stack.as_mut()) {
+      if let Some(durability) = stack.durability {
+        stack.durability = Some(durability.saturating_sub(1));
+        if durability <= 1 {
+          self.message = format!("{} broke", stack.item.name);
+          self.message_ticks = MESSAGE_TICKS;
+          self.inventory.slots[slot] = None;
+          self.selected = None;
+        }
+      }
+    }
+  }
+
+  // place the selected item as a tile, using it up. returns whether it could be placed
+  pub fn place (&mut self, world: &mut world::World, tile_x: i32, tile_y: i32) -> bool {
+    let slot = match self.selected {
+      Some(slot) => slot,
+      None => return false
+    };
+    let item_id = match self.inventory.get(slot) {
+      Some(stack) => stack.item.id,
+      None => return false
+    };
+    let places = match tiles::registry().item_definition(item_id).and_then(|item| item.places.as_ref()) {
+      Some(name) => tiles::registry().tile(name),
+      None => return false
+    };
+    // don't trap the player inside something solid
+    if places.solid && collision::Aabb::from_tile(tile_x, tile_y, &places.collision).overlaps(&self.feet_box()) {
+      return false;
+    }
+    if !world.can_place(tile_x, tile_y, places.id) {
+      return false;
+    }
+    world.place(tile_x, tile_y, places.id);
+    // placing a campfire moves where the player comes back after dying
+    if tiles::registry().tile_definition(places.id).is_some_and(|tile| tile.respawn_point) {
+      self.respawn_point = Some((tile_x, tile_y));
+    }
+    self.inventory.take(slot, 1);
+    // keep holding the same kind of item if there's more of it somewhere else
+    if self.inventory.get(slot).is_none() {
+      self.selected = self.inventory.find(item_id);
+    }
+    true
+  }
+
+  // try to craft a recipe, leaving a message about how it went for the crafting screen
+  pub fn craft (&mut self, world: &mut world::World, recipe: &tiles::RecipeDefinition) {
+    let (feet_x, feet_y) = self.feet_tile();
+    self.crafting_message = match crafting::craft(&mut self.inventory, recipe, world, feet_x, feet_y) {
+      Ok(output) => format!("Crafted {} {}", output.count, output.item.name),
+      Err(err) => err.to_string()
+    };
+  }
+
+  // hold the item in an inventory slot, or put it away if it's already held
+  pub fn select (&mut self, slot: usize) {
+    if self.selected == Some(slot) || self.inventory.get(slot).is_none() {
+      self.selected = None;
+    } else {
+      self.selected = Some(slot);
+    }
+  }
+
+  // clicking a slot in the inventory screen moves the held stack onto it, or holds it if nothing is held
+  pub fn click_slot (&mut self, slot: usize) {
+    match self.selected {
+      Some(held) if held != slot && self.inventory.get(held).is_some() => {
+        self.inventory.merge(held, slot);
+        self.selected = Some(slot);
+      },
+      _ => self.select(slot)
+    }
+  }
+
+  // an action's key was pressed, so hold the action until it's released
+  pub fn action_pressed (&mut self, action: input::Action) {
+    // turn to face the way we're moving
+    if let Some(direction) = action.direction() {
+      self.facing = direction;
+    }
+    self.actions_held.insert(action);
+  }
+
+  pub fn action_released (&mut self, action: input::Action) {
+    self.actions_held.remove(&action);
+  }
+
+  // a controller's stick moved, so face whichever way it's pushed furthest
+  pub fn tilt (&mut self, stick_x: f32, stick_y: f32) {
+    self.stick = (stick_x, stick_y);
+    if stick_x.abs() > stick_y.abs() {
+      self.facing = (stick_x.signum() as i32, 0);
+    } else if stick_y != 0. {
+      self.facing = (0, stick_y.signum() as i32);
+    }
+  }
+
+  pub fn gen_ui_vertices (&mut self, mouse_pos: [f32; 2], mouse_down: bool, control_flow: &mut winit::event_loop::ControlFlow, world: &mut world::World) -> (Vec<render::Vertex>, Vec<u16>) {
 
     let mut vertices: Vec<render::Vertex> = Vec::new();
     let mut indices: Vec<u16> = Vec::new();
     // create variables for texture locations
-    let tile_width: f32 = 4. / self.width as f32;
-    let tile_height: f32 = 4. / self.height as f32;
+    let tile_width: f32 = 4. / self.camera.width as f32;
+    let tile_height: f32 = 4. / self.camera.height as f32;
     let texture_width: f32 = 8. / render::TILESET_WIDTH as f32;
     let texture_height: f32 = 8. / render::TILESET_HEIGHT as f32;
 
@@ -170,16 +677,70 @@ impl Player<'_> {
       // get positions for heart
       let heart_x: f32 = -1.0 + tile_width * heart as f32 + (tile_width / 10.);
       let heart_y: f32 = 1.0 - tile_height / 10.;
+      // each heart is 2 health, so pick a full, half or empty heart for what's left of it
+      let heart_health = self.health - heart as f32 * 2.;
+      let heart_tex_x: f32 = if heart_health >= 2. { 2. } else if heart_health >= 1. { 3. } else { 4. };
       // add the vertices
-      vertices.push(render::Vertex { pos: [ heart_x, heart_y ], tex_coords: [ texture_width * 2., texture_height * 4. ], animation_frames: 1.}); // top left
-      vertices.push(render::Vertex { pos: [ heart_x, heart_y - tile_height * 9. / 10. ], tex_coords: [ texture_width * 2., texture_height * 5. ], animation_frames: 1.}); // bottom left
-      vertices.push(render::Vertex { pos: [ heart_x + tile_width * 9. / 10., heart_y - tile_height * 9. / 10. ], tex_coords: [ texture_width * 3., texture_height * 5. ], animation_frames: 1.}); // bottom right
-      vertices.push(render::Vertex { pos: [ heart_x + tile_width * 9. / 10., heart_y ], tex_coords: [ texture_width * 3., texture_height * 4. ], animation_frames: 1.}); // top right
+      vertices.push(render::Vertex { pos: [ heart_x, heart_y ], tex_coords: [ texture_width * heart_tex_x, texture_height * 4. ], animation_frames: 1.}); // top left
+      vertices.push(render::Vertex { pos: [ heart_x, heart_y - tile_height * 9. / 10. ], tex_coords: [ texture_width * heart_tex_x, texture_height * 5. ], animation_frames: 1.}); // bottom left
+      vertices.push(render::Vertex { pos: [ heart_x + tile_width * 9. / 10., heart_y - tile_height * 9. / 10. ], tex_coords: [ texture_width * (heart_tex_x + 1.), texture_height * 5. ], animation_frames: 1.}); // bottom right
+      vertices.push(render::Vertex { pos: [ heart_x + tile_width * 9. / 10., heart_y ], tex_coords: [ texture_width * (heart_tex_x + 1.), texture_height * 4. ], animation_frames: 1.}); // top right
       // add in the indices
       let len = vertices.len();
       indices.append(&mut vec![ (len - 4).try_into().unwrap(), (len - 3).try_into().unwrap(), (len - 2).try_into().unwrap(), (len - 4).try_into().unwrap(), (len - 2).try_into().unwrap(), (len - 1).try_into().unwrap() ]);
     }
 
+    // hunger, thirst and stamina go in rows under the hearts, each an icon and a bar showing how full it is
+    let meters = [(self.hunger, (15., 6.), (14., 4.)), (self.thirst, (10., 9.), (15., 4.)), (self.stamina, (11., 9.), (14., 6.))];
+    for (row, (meter, (icon_x, icon_y), (fill_x, fill_y))) in meters.iter().enumerate() {
+      let row_y: f32 = 1.0 - tile_height * (1. + 0.5 * (row + 1) as f32);
+      let bar_x: f32 = -1.0 + tile_width * 0.7;
+      let bar_width: f32 = tile_width * 2.3;
+      let mut meter_vertices = ui::Group {
+        children: vec![
+          ui::Bar { pos: [-1.0 + tile_width / 10., row_y], width: tile_width * 0.45, height: tile_height * 0.45, tex_coords: [texture_width * icon_x, texture_height * icon_y] }.gen_vertices(),
+          ui::Bar { pos: [bar_x, row_y + tile_height * 0.1], width: bar_width, height: tile_height * 0.25, tex_coords: [texture_width * 5., texture_height * 4.] }.gen_vertices(),
+          ui::Bar { pos: [bar_x, row_y + tile_height * 0.1], width: bar_width * meter / MAX_METER, height: tile_height * 0.25, tex_coords: [texture_width * fill_x, texture_height * fill_y] }.gen_vertices()
+        ]
+      }.gen_vertices();
+      let index_start: u16 = vertices.len().try_into().unwrap();
+      let index_end: u16 = index_start + meter_vertices.len() as u16;
+      vertices.append(&mut meter_vertices);
+      indices.extend(index_start..index_end);
+    }
+
+    // show the held item in the top right
+    if let Some(item) = self.selected.and_then(|slot| self.inventory.get(slot)).map(|stack| stack.item) {
+      let item_x: f32 = 1.0 - tile_width - (tile_width / 10.);
+      let item_y: f32 = 1.0 - tile_height / 10.;
+      let item_tex_x: f32 = texture_width * item.ts_coord_x as f32;
+      let item_tex_y: f32 = texture_height * item.ts_coord_y as f32;
+      vertices.push(render::Vertex { pos: [ item_x, item_y ], tex_coords: [ item_tex_x, item_tex_y ], animation_frames: 1.}); // top left
+      vertices.push(render::Vertex { pos: [ item_x, item_y - tile_height * 9. / 10. ], tex_coords: [ item_tex_x, item_tex_y + texture_height ], animation_frames: 1.}); // bottom left
+      vertices.push(render::Vertex { pos: [ item_x + tile_width * 9. / 10., item_y - tile_height * 9. / 10. ], tex_coords: [ item_tex_x + texture_width, item_tex_y + texture_height ], animation_frames: 1.}); // bottom right
+      vertices.push(render::Vertex { pos: [ item_x + tile_width * 9. / 10., item_y ], tex_coords: [ item_tex_x + texture_width, item_tex_y ], animation_frames: 1.}); // top right
+      let len = vertices.len();
+      indices.append(&mut vec![ (len - 4).try_into().unwrap(), (len - 3).try_into().unwrap(), (len - 2).try_into().unwrap(), (len - 4).try_into().unwrap(), (len - 2).try_into().unwrap(), (len - 1).try_into().unwrap() ]);
+    }
+
+    // let the player know clicking will build instead of harvest
+    if self.building && !self.paused {
+      let mut building_vertices = ui::Label { pos: [1.0 - tile_width * 2., 1.0 - tile_height * 1.25], text: String::from("Building"), size_x: tile_width / 4., size_y: tile_height / 4. }.gen_vertices();
+      let index_start: u16 = vertices.len().try_into().unwrap();
+      let index_end: u16 = index_start + building_vertices.len() as u16;
+      vertices.append(&mut building_vertices);
+      indices.extend(index_start..index_end);
+    }
+
+    // show what just happened for a little while
+    if self.message_ticks > 0 && !self.paused {
+      let mut message_vertices = ui::Label { pos: [0., -0.75], text: self.message.clone(), size_x: tile_width / 3., size_y: tile_height / 3. }.gen_vertices();
+      let index_start: u16 = vertices.len().try_into().unwrap();
+      let index_end: u16 = index_start + message_vertices.len() as u16;
+      vertices.append(&mut message_vertices);
+      indices.extend(index_start..index_end);
+    }
+
     // if game is paused, add text and buttons
     if self.paused {
 
@@ -193,6 +754,9 @@ impl Player<'_> {
       indices.append(&mut vec![ (len - 4).try_into().unwrap(), (len - 3).try_into().unwrap(), (len - 2).try_into().unwrap(), (len - 4).try_into().unwrap(), (len - 2).try_into().unwrap(), (len - 1).try_into().unwrap() ]);
 
       let mut pause_ui_vertices: Vec<render::Vertex> = Vec::new();
+      // buttons count themselves off against a copy of the focus, since their clicks can change anything on the player
+      let mut focus = self.focus.begin();
+      let screen = self.pause_type;
 
       pause_ui_vertices = match self.pause_type {
         PauseType::Pause => {
@@ -201,19 +765,161 @@ impl Player<'_> {
           ui::Group {
             children: vec![
               ui::Label { pos: [0., 0.5], text: String::from("Paused"), size_x: tile_width, size_y: tile_height }.gen_vertices(),
-              ui::Button { pos: [0., 0.], label: String::from("Resume"), click: || { self.paused = false; } }.gen_vertices(&mouse_pos, mouse_down),
-              ui::Button { pos: [0., -0.2], label: String::from("Save Game"), click: || { self.write_out_gamedata(); } }.gen_vertices(&mouse_pos, mouse_down),
-              ui::Button { pos: [0., -0.4], label: String::from("Load Game"), click: || { self.load_gamedata( self.read_gamedata()); self.paused = false; } }.gen_vertices(&mouse_pos, mouse_down),
-              ui::Button { pos: [0., -0.6], label: String::from("Quit"), click: || { *control_flow = winit::event_loop::ControlFlow::Exit; } }.gen_vertices(&mouse_pos, mouse_down)
+              ui::Button { pos: [0., 0.], label: String::from("Resume"), click: || { self.paused = false; } }.gen_vertices(&mouse_pos, mouse_down, &mut focus),
+              ui::Label { pos: [0., 0.25], text: self.save_message.clone(), size_x: tile_width / 3., size_y: tile_height / 3. }.gen_vertices(),
+              ui::Button { pos: [0., -0.2], label: String::from("Save Game"), click: || {
+                self.save_message = match self.write_out_gamedata(world) {
+                  Ok(()) => String::from("Game saved"),
+                  Err(err) => format!("Couldn't save: {}", err)
+                };
+              } }.gen_vertices(&mouse_pos, mouse_down, &mut focus),
+              ui::Button { pos: [0., -0.4], label: String::from("Worlds"), click: || { self.open_worlds(); } }.gen_vertices(&mouse_pos, mouse_down, &mut focus),
+              ui::Button { pos: [0., -0.6], label: String::from("Controls"), click: || { self.pause_type = PauseType::Controls; } }.gen_vertices(&mouse_pos, mouse_down, &mut focus),
+              ui::Button { pos: [0., -0.8], label: String::from("Quit"), click: || { *control_flow = winit::event_loop::ControlFlow::Exit; } }.gen_vertices(&mouse_pos, mouse_down, &mut focus)
             ]
           }.gen_vertices()
           
         },
         PauseType::Inventory => {
-          ui::Group {
+          // clicking a slot holds that item, or moves the held item there
+          let mut clicked: Option<usize> = None;
+          let inventory_vertices = ui::Group {
             children: vec![
               ui::Label { pos: [0., 0.75], text: String::from("Inventory"), size_x: tile_width, size_y: tile_height }.gen_vertices(),
-              ui::Inventory { pos: [0., 0.25], size_x: tile_width, size_y: tile_height, inventory: &self.inventory }.gen_vertices(&mouse_pos)
+              ui::Inventory { pos: [0., 0.25], size_x: tile_width, size_y: tile_height, inventory: &self.inventory, selected: self.selected, click: |slot| { clicked = Some(slot); } }.gen_vertices(&mouse_pos, mouse_down, &mut focus)
+            ]
+          }.gen_vertices();
+          if let Some(slot) = clicked {
+            self.click_slot(slot);
+          }
+          inventory_vertices
+        },
+        PauseType::Crafting => {
+          let recipes = tiles::registry().recipes();
+          let mut clicked: Option<usize> = None;
+          let mut children = vec![
+            ui::Label { pos: [0., 0.75], text: String::from("Crafting"), size_x: tile_width, size_y: tile_height }.gen_vertices()
+          ];
+          // a button to craft each recipe, with what it needs next to it, in two columns
+          let rows = recipes.len().div_ceil(2);
+          for (i, recipe) in recipes.iter().enumerate() {
+            let recipe_x = if i < rows { -0.75 } else { 0.25 };
+            let recipe_y = 0.5 - 0.2 * (i % rows) as f32;
+            let output = tiles::registry().item(&recipe.output);
+            let label = if recipe.count > 1 { format!("{} {}", output.name, recipe.count) } else { String::from(output.name) };
+            children.push(ui::Button { pos: [recipe_x, recipe_y], label, click: || { clicked = Some(i); } }.gen_vertices(&mouse_pos, mouse_down, &mut focus));
+            let needs: Vec<String> = recipe.inputs.iter().map(|input| format!("{} {}", input.count, tiles::registry().item(&input.item).name)).collect();
+            children.push(ui::Label { pos: [recipe_x + 0.45, recipe_y + 0.025], text: needs.join(" "), size_x: tile_width / 4., size_y: tile_height / 4. }.gen_vertices());
+            if let Some(station) = &recipe.station {
+              let station_name = tiles::registry().tile_id(station).and_then(|id| tiles::registry().tile_definition(id)).map_or(station.as_str(), |tile| tile.display_name.as_str());
+              children.push(ui::Label { pos: [recipe_x + 0.45, recipe_y - 0.025], text: format!("at {}", station_name), size_x: tile_width / 4., size_y: tile_height / 4. }.gen_vertices());
+            }
+          }
+          if let Some(i) = clicked {
+            self.craft(world, &recipes[i]);
+          }
+          children.push(ui::Label { pos: [0., -0.85], text: self.crafting_message.clone(), size_x: tile_width / 3., size_y: tile_height / 3. }.gen_vertices());
+          ui::Group { children }.gen_vertices()
+        },
+        PauseType::Controls => {
+          let mut clicked: Option<input::Action> = None;
+          let mut reset = false;
+          let mut back = false;
+          let message = match self.rebinding {
+            Some(action) => format!("Press a key or button for {} or pause to cancel", action.name()),
+            None => String::from("Click an action then press a key or button to bind it")
+          };
+          let mut children = vec![
+            ui::Label { pos: [0., 0.85], text: String::from("Controls"), size_x: tile_width, size_y: tile_height }.gen_vertices(),
+            ui::Label { pos: [0., 0.7], text: message, size_x: tile_width / 3., size_y: tile_height / 3. }.gen_vertices()
+          ];
+          // a button for each action with its keys next to it, in two columns
+          let rows = input::Action::ALL.len().div_ceil(2);
+          for (i, action) in input::Action::ALL.iter().enumerate() {
+            let action_x = if i < rows { -0.75 } else { 0.25 };
+            let action_y = 0.55 - 0.145 * (i % rows) as f32;
+            children.push(ui::Button { pos: [action_x, action_y], label: String::from(action.name()), click: || { clicked = Some(*action); } }.gen_vertices(&mouse_pos, mouse_down, &mut focus));
+            children.push(ui::Label { pos: [action_x + 0.4, action_y + 0.025], text: self.bindings.describe(*action), size_x: tile_width / 4., size_y: tile_height / 4. }.gen_vertices());
+            children.push(ui::Label { pos: [action_x + 0.4, action_y - 0.025], text: self.gamepad_bindings.describe(*action), size_x: tile_width / 4., size_y: tile_height / 4. }.gen_vertices());
+          }
+          children.push(ui::Button { pos: [-0.25, -0.92], label: String::from("Reset"), click: || { reset = true; } }.gen_vertices(&mouse_pos, mouse_down, &mut focus));
+          children.push(ui::Button { pos: [0.25, -0.92], label: String::from("Back"), click: || { back = true; } }.gen_vertices(&mouse_pos, mouse_down, &mut focus));
+          // clicking the action that's waiting for a key stops waiting
+          if let Some(action) = clicked {
+            self.rebinding = if self.rebinding == Some(action) { None } else { Some(action) };
+          }
+          if reset {
+            self.bindings = input::Bindings::default();
+            self.gamepad_bindings = input::Bindings::default();
+            self.rebinding = None;
+          }
+          if back {
+            self.pause_type = PauseType::Pause;
+            self.rebinding = None;
+          }
+          ui::Group { children }.gen_vertices()
+        },
+        PauseType::Worlds => {
+          let mut clicked: Option<WorldButton> = None;
+          let list = &self.world_list;
+          let mut children = vec![
+            ui::Label { pos: [0., 0.85], text: String::from("Worlds"), size_x: tile_width, size_y: tile_height }.gen_vertices()
+          ];
+          if list.worlds.is_empty() {
+            children.push(ui::Label { pos: [0., 0.4], text: String::from("No saved worlds yet"), size_x: tile_width / 3., size_y: tile_height / 3. }.gen_vertices());
+          }
+          // a button for each world on this page, in two columns
+          let first = list.page * WORLDS_PER_PAGE;
+          let rows = WORLDS_PER_PAGE / 2;
+          for (i, info) in list.worlds.iter().enumerate().skip(first).take(WORLDS_PER_PAGE) {
+            let row = i - first;
+            let world_x = if row < rows { -0.22 } else { 0.22 };
+            let world_y = 0.6 - 0.17 * (row % rows) as f32;
+            // only as much of the name as fits on a button
+            let label: String = info.name.chars().take(12).collect();
+            children.push(ui::Button { pos: [world_x, world_y], label, click: || { clicked = Some(WorldButton::Select(i)); } }.gen_vertices(&mouse_pos, mouse_down, &mut focus));
+          }
+          if list.page > 0 {
+            children.push(ui::Button { pos: [-0.7, 0.35], label: String::from("Previous"), click: || { clicked = Some(WorldButton::PreviousPage); } }.gen_vertices(&mouse_pos, mouse_down, &mut focus));
+          }
+          if first + WORLDS_PER_PAGE < list.worlds.len() {
+            children.push(ui::Button { pos: [0.7, 0.35], label: String::from("Next"), click: || { clicked = Some(WorldButton::NextPage); } }.gen_vertices(&mouse_pos, mouse_down, &mut focus));
+          }
+          // everything about the picked world, with the name being typed in its place while it's renamed
+          if let Some(info) = list.selected.and_then(|i| list.worlds.get(i)) {
+            let name = match &list.renaming {
+              Some(name) => format!("{}_", name),
+              None => info.name.clone()
+            };
+            children.push(ui::Label { pos: [0., -0.1], text: name, size_x: tile_width / 2., size_y: tile_height / 2. }.gen_vertices());
+            children.push(ui::Label { pos: [0., -0.2], text: info.describe(), size_x: tile_width / 3., size_y: tile_height / 3. }.gen_vertices());
+          }
+          children.push(ui::Label { pos: [0., -0.3], text: self.save_message.clone(), size_x: tile_width / 3., size_y: tile_height / 3. }.gen_vertices());
+          let rename_label = if list.renaming.is_some() { "Done" } else { "Rename" };
+          let delete_label = if list.confirm_delete { "Confirm" } else { "Delete" };
+          children.push(ui::Button { pos: [-0.6, -0.5], label: String::from("Load"), click: || { clicked = Some(WorldButton::Load); } }.gen_vertices(&mouse_pos, mouse_down, &mut focus));
+          children.push(ui::Button { pos: [-0.2, -0.5], label: String::from(rename_label), click: || { clicked = Some(WorldButton::Rename); } }.gen_vertices(&mouse_pos, mouse_down, &mut focus));
+          children.push(ui::Button { pos: [0.2, -0.5], label: String::from("Duplicate"), click: || { clicked = Some(WorldButton::Duplicate); } }.gen_vertices(&mouse_pos, mouse_down, &mut focus));
+          children.push(ui::Button { pos: [0.6, -0.5], label: String::from(delete_label), click: || { clicked = Some(WorldButton::Delete); } }.gen_vertices(&mouse_pos, mouse_down, &mut focus));
+          children.push(ui::Button { pos: [-0.25, -0.75], label: String::from("New World"), click: || { clicked = Some(WorldButton::New); } }.gen_vertices(&mouse_pos, mouse_down, &mut focus));
+          children.push(ui::Button { pos: [0.25, -0.75], label: String::from("Back"), click: || { clicked = Some(WorldButton::Back); } }.gen_vertices(&mouse_pos, mouse_down, &mut focus));
+          if let Some(button) = clicked {
+            self.click_world_button(world, button);
+          }
+          ui::Group { children }.gen_vertices()
+        },
+        PauseType::Dead => {
+          let cause = match &self.death_stats.last_cause {
+            Some(cause) if !cause.is_empty() => format!("Killed by {}", cause),
+            _ => String::from("You ran out of health")
+          };
+          ui::Group {
+            children: vec![
+              ui::Label { pos: [0., 0.5], text: String::from("You Died"), size_x: tile_width, size_y: tile_height }.gen_vertices(),
+              ui::Label { pos: [0., 0.3], text: cause, size_x: tile_width / 3., size_y: tile_height / 3. }.gen_vertices(),
+              ui::Label { pos: [0., 0.2], text: format!("Deaths {}", self.death_stats.deaths), size_x: tile_width / 3., size_y: tile_height / 3. }.gen_vertices(),
+              ui::Button { pos: [0., -0.1], label: String::from("Respawn"), click: || { self.respawn(world); } }.gen_vertices(&mouse_pos, mouse_down, &mut focus),
+              ui::Button { pos: [0., -0.3], label: String::from("Quit"), click: || { *control_flow = winit::event_loop::ControlFlow::Exit; } }.gen_vertices(&mouse_pos, mouse_down, &mut focus)
             ]
           }.gen_vertices()
         },
@@ -222,6 +928,9 @@ impl Player<'_> {
         }
       };
 
+      // anything picked on one screen means nothing on the next
+      self.focus = if self.paused && self.pause_type == screen { focus.finish() } else { ui::Focus::default() };
+
       let index_start: u16 = vertices.len().try_into().unwrap();
       let pause_label_length: u16 = pause_ui_vertices.len().try_into().unwrap();
       let index_end: u16 = index_start + pause_label_length;
@@ -235,26 +944,212 @@ impl Player<'_> {
     ( vertices.iter().cloned().collect(), indices.iter().cloned().collect() )
   }
 
-  pub fn write_out_gamedata (&self) {
-    let gamedata = GameData { health: self.health, max_health: self.max_health, seed: self.seed, x: self.x, y: self.y, time: self.uniforms.time };
-    let file_string = serde_yaml::to_string(&gamedata).unwrap();
-    fs::create_dir("worlds").unwrap_or(());
-    fs::write(format!("worlds/{}.yaml", self.world_name), &file_string).unwrap();
+  // everything about the player and the world that goes in a save
+  pub fn gamedata (&self, world: &mut world::World) -> GameData {
+    GameData {
+      version: save::SAVE_VERSION,
+      health: self.health, max_health: self.max_health, seed: self.seed, time: self.time,
+      position: (self.x, self.y),
+      settings: world.settings().clone(),
+      chunks: world.changed_chunks(),
+      flat: world.flat,
+      inventory: Some(self.inventory.to_saved()),
+      piles: world.saved_piles(),
+      rules: self.rules.clone(),
+      death_stats: self.death_stats.clone(),
+      spawn_point: Some(self.spawn_point),
+      respawn_point: self.respawn_point,
+      hunger: self.hunger, thirst: self.thirst, stamina: self.stamina
+    }
+  }
+
+  pub fn write_out_gamedata (&self, world: &mut world::World) -> Result<(), save::SaveError> {
+    self.saves.write(&self.world_name, &self.gamedata(world))
   }
-  pub fn read_gamedata (&self) -> GameData {
-    let file_string = fs::read_to_string(format!("worlds/{}.yaml", self.world_name)).unwrap();
-    fs::create_dir("worlds").unwrap_or(());
-    let result: GameData = serde_yaml::from_str(&file_string).unwrap();
-    println!("{:?}", result);
-    result
+  // read the save for this world, upgrading it if it's from an older version of the game
+  pub fn read_gamedata (&self) -> Result<GameData, save::SaveError> {
+    self.saves.read(&self.world_name)
   }
-  pub fn load_gamedata (&mut self, gamedata: GameData) -> Vec<Vec<tiles::TileProperties>> {
-    self.x = gamedata.x;
-    self.y = gamedata.y;
+  pub fn load_gamedata (&mut self, gamedata: GameData, world: &mut world::World) -> save::Skipped {
+    let (x, y) = gamedata.position;
+    self.x = x;
+    self.y = y;
+    self.prev_x = x;
+    self.prev_y = y;
+    self.x_speed = 0.;
+    self.y_speed = 0.;
+    let (camera_x, camera_y) = self.camera_target();
+    self.camera.snap_to(camera_x, camera_y);
     self.max_health = gamedata.max_health;
     self.health = gamedata.health;
-    self.uniforms.time = gamedata.time;
-    worldgen::elevation_to_tiles(worldgen::generate_perlin(1000, 1000, gamedata.seed))
+    self.hunger = gamedata.hunger;
+    self.thirst = gamedata.thirst;
+    self.stamina = gamedata.stamina;
+    self.seed = gamedata.seed;
+    self.time = gamedata.time;
+    let (inventory, items) = match &gamedata.inventory {
+      Some(saved) => inventory::Inventory::from_saved(saved, inventory::INVENTORY_SLOTS),
+      None => (starting_inventory(), Vec::new())
+    };
+    self.inventory = inventory;
+    self.selected = None;
+    // swap out the world for a fresh one, chunks will be regenerated from the saved seed and then changed
+    *world = world::World::new(gamedata.seed, gamedata.settings);
+    world.flat = gamedata.flat;
+    let tiles = world.apply_chunk_changes(&gamedata.chunks);
+    world.load_piles(&gamedata.piles);
+    self.rules = gamedata.rules;
+    self.death_stats = gamedata.death_stats;
+    self.spawn_point = gamedata.spawn_point.unwrap_or_else(|| worldgen::find_spawn(world));
+    self.respawn_point = gamedata.respawn_point;
+    save::Skipped { tiles, items }
+  }
+
+  // go to the worlds screen, with the world being played picked if it's been saved
+  pub fn open_worlds (&mut self) {
+    self.pause_type = PauseType::Worlds;
+    self.save_message = String::new();
+    let world_name = self.world_name.clone();
+    self.refresh_worlds(Some(&world_name));
+  }
+
+  // look through the saved worlds again, picking one by name
+  pub fn refresh_worlds (&mut self, select: Option<&str>) {
+    let worlds = self.saves.list().unwrap_or_else(|err| {
+      self.save_message = format!("Couldn't list the worlds: {}", err);
+      Vec::new()
+    });
+    let selected = select.and_then(|name| worlds.iter().position(|info| info.name == name));
+    // show the page the picked world is on
+    let page = selected.map_or(0, |i| i / WORLDS_PER_PAGE);
+    self.world_list = WorldList { worlds, selected, page, renaming: None, confirm_delete: false };
+  }
+
+  // the name of the world picked on the worlds screen
+  pub fn selected_world (&self) -> Option<String> {
+    self.world_list.selected.and_then(|i| self.world_list.worlds.get(i)).map(|info| info.name.clone())
+  }
+
+  // stop playing this world and play a saved one instead. whatever hasn't been saved here is lost
+  pub fn load_world (&mut self, world: &mut world::World, name: &str) -> Result<save::Skipped, save::SaveError> {
+    let gamedata = self.saves.read(name)?;
+    let skipped = self.load_gamedata(gamedata, world);
+    self.world_name = String::from(name);
+    Ok(skipped)
+  }
+
+  /**
+   * Leave this world for a brand new one with its own seed, generated with the same settings. The player starts
+   * over, keeping only their controls and rules, and the world is saved straight away so it's in the list
+   */
+  pub fn new_world (&mut self, world: &mut world::World, seed: u32) -> Result<(), save::SaveError> {
+    *world = world::World::new(seed, world.settings().clone());
+    let mut player = Player::new(self.camera.width, self.camera.height, seed);
+    player.camera.settings = self.camera.settings.clone();
+    player.rules = self.rules.clone();
+    player.bindings = self.bindings.clone();
+    player.gamepad_bindings = self.gamepad_bindings.clone();
+    player.world_name = self.saves.unused_name(DEFAULT_WORLD_NAME);
+    player.saves = self.saves.clone();
+    player.spawn(world);
+    *self = player;
+    self.write_out_gamedata(world)
+  }
+
+  pub fn rename_world (&mut self, name: &str, new_name: &str) -> Result<(), save::SaveError> {
+    self.saves.rename(name, new_name)?;
+    // the world being played keeps saving to the same file
+    if self.world_name == name {
+      self.world_name = String::from(new_name);
+    }
+    Ok(())
+  }
+
+  // type into the name of the world being renamed. enter renames it, and escape stops without renaming
+  pub fn type_character (&mut self, character: char) {
+    match character {
+      '\r' | '\n' => self.finish_renaming(),
+      '\u{1b}' => self.world_list.renaming = None,
+      _ => if let Some(name) = &mut self.world_list.renaming {
+        if character == '\u{8}' || character == '\u{7f}' {
+          name.pop();
+        } else if (character.is_ascii_alphanumeric() || " -_".contains(character)) && name.len() < MAX_WORLD_NAME_LENGTH {
+          name.push(character);
+        }
+      }
+    }
+  }
+
+  pub fn finish_renaming (&mut self) {
+    if let (Some(new_name), Some(name)) = (self.world_list.renaming.take(), self.selected_world()) {
+      let new_name = new_name.trim();
+      self.save_message = match self.rename_world(&name, new_name) {
+        Ok(()) => format!("Renamed {} to {}", name, new_name),
+        Err(err) => format!("Couldn't rename {}: {}", name, err)
+      };
+      let selected = if self.saves.exists(new_name) { new_name } else { &name };
+      self.refresh_worlds(Some(selected));
+    }
+  }
+
+  // do what a button on the worlds screen does
+  pub fn click_world_button (&mut self, world: &mut world::World, button: WorldButton) {
+    // anything else that's clicked stops a rename or delete that was started
+    if button != WorldButton::Rename {
+      self.world_list.renaming = None;
+    }
+    if button != WorldButton::Delete {
+      self.world_list.confirm_delete = false;
+    }
+    let selected = self.selected_world();
+    match (button, selected) {
+      (WorldButton::Select(i), _) => self.world_list.selected = Some(i),
+      (WorldButton::PreviousPage, _) => self.world_list.page = self.world_list.page.saturating_sub(1),
+      (WorldButton::NextPage, _) => self.world_list.page += 1,
+      (WorldButton::Load, Some(name)) => match self.load_world(world, &name) {
+        Ok(skipped) => {
+          self.save_message = String::new();
+          self.pause_type = PauseType::Pause;
+          self.paused = false;
+          // the game carries on, so say what was missing while playing
+          if !skipped.is_empty() {
+            self.message = format!("Loaded {}, but {}", name, skipped);
+            self.message_ticks = MESSAGE_TICKS;
+          }
+        },
+        Err(err) => self.save_message = format!("Couldn't load {}: {}", name, err)
+      },
+      (WorldButton::Rename, Some(name)) => match self.world_list.renaming {
+        Some(_) => self.finish_renaming(),
+        None => self.world_list.renaming = Some(name)
+      },
+      (WorldButton::Duplicate, Some(name)) => {
+        let new_name = self.saves.unused_name(&format!("{} Copy", name));
+        self.save_message = match self.saves.duplicate(&name, &new_name) {
+          Ok(()) => format!("Copied {} to {}", name, new_name),
+          Err(err) => format!("Couldn't copy {}: {}", name, err)
+        };
+        self.refresh_worlds(Some(&new_name));
+      },
+      (WorldButton::Delete, Some(name)) => if self.world_list.confirm_delete {
+        self.save_message = match self.saves.delete(&name) {
+          Ok(()) => format!("Deleted {}", name),
+          Err(err) => format!("Couldn't delete {}: {}", name, err)
+        };
+        self.refresh_worlds(None);
+      } else {
+        self.world_list.confirm_delete = true;
+      },
+      // the new world is played straight away, unless it couldn't be saved
+      (WorldButton::New, _) => if let Err(err) = self.new_world(world, rand::random::<u32>()) {
+        self.open_worlds();
+        self.paused = true;
+        self.save_message = format!("Couldn't save the new world: {}", err);
+      },
+      (WorldButton::Back, _) => self.pause_type = PauseType::Pause,
+      // the rest need a world to be picked first
+      (_, None) => self.save_message = String::from("Pick a world first")
+    }
   }
 
 }
@@ -268,7 +1163,10 @@ pub struct Uniforms {
   pub time: f32,
   pub light_intensity: [f32; 3],
   // needed to fix an issue with the shader that I'm not sure about
-  pub unused: f32
+  pub unused: f32,
+  pub player_offset: [f32; 2], // where the middle of the player is on screen
+  // uniforms are padded out to a multiple of 16 bytes
+  pub padding: [f32; 2]
 }
 
 impl Uniforms {
@@ -278,17 +1176,25 @@ impl Uniforms {
       is_swimming: 0,
       time: 0.,
       light_intensity: light_intensity(0.),
-      unused: 0.
+      unused: 0.,
+      player_offset: [0., 0.],
+      padding: [0., 0.]
     }
   }
 }
 
-// create a light intensity for the shader based on current time
-pub fn light_intensity (time: f32) -> [f32; 3] {
+// what a new player starts out carrying
+pub fn starting_inventory () -> inventory::Inventory<'static> {
+  let mut inventory = inventory::Inventory::new(inventory::INVENTORY_SLOTS);
+  inventory.add(inventory::ItemStack::new(tiles::registry().item("sword"), 1));
+  inventory.add(inventory::ItemStack::new(tiles::registry().item("stick"), 1));
+  inventory
+}
 
-  let day_length: f32 = 36.; // length of the day (currently a minute)
+// create a light intensity for the shader based on how many seconds the game has been played for
+pub fn light_intensity (time: f32) -> [f32; 3] {
 
-  let offset_time = 2. * PI / day_length * time; // so that the cycle happens every length of day instead of pi
+  let offset_time = 2. * PI / DAY_LENGTH * time; // so that the cycle happens every length of day instead of pi
 
   let r: f32 = 1_f32.min(0.03_f32.max(offset_time.sin() + 1.)); // r value; sliced sine
   let g: f32 = 1_f32.min(0.06_f32.max(offset_time.sin() + 0.9)); // g value; sliced sine, make it rise slower for sunrise / sunset
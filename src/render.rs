@@ -1,8 +1,10 @@
 use std::{borrow::Cow, convert::TryInto, mem};
 
+use crate::camera;
 use crate::player;
 use crate::tiles;
 use crate::tiles::TileInstance;
+use crate::world;
 
 use wgpu::util::DeviceExt;
 use bytemuck::{Pod, Zeroable};
@@ -69,7 +71,7 @@ impl Render {
   /** 
   * Create an instance of renderer
   */
-  pub async fn new (window: &winit::window::Window, world: &mut Vec<Vec<tiles::TileProperties>>, play: &mut player::Player<'_>,  cam_width: i32, cam_height: i32) -> Self {
+  pub async fn new (window: &winit::window::Window, world: &mut world::World, play: &mut player::Player<'_>,  cam_width: i32, cam_height: i32) -> Self {
 
     let size = window.inner_size();
     // wgpu stuff
@@ -121,7 +123,7 @@ impl Render {
     let ui_index_count = ui_indices.len();
 
     // make instance data
-    let instances = gen_tile_instances(world, 0, 0, cam_width, cam_height);
+    let instances = gen_tile_instances(world, &play.camera);
 
     // buffers
     let vertex_buf = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
@@ -423,22 +425,24 @@ impl Render {
   }
 
   /**
-  * Update vertices based on current camera position
+  * Update vertices based on current camera position. Alpha is how far it is between the last tick and the next
   */
-  pub fn update (&mut self, world: &mut Vec<Vec<tiles::TileProperties>>, player: &mut player::Player, mouse_pos: [f32; 2], mouse_down: bool, control_flow: &mut winit::event_loop::ControlFlow) {
+  pub fn update (&mut self, world: &mut world::World, player: &mut player::Player, alpha: f32, mouse_pos: [f32; 2], mouse_down: bool, control_flow: &mut winit::event_loop::ControlFlow) {
     // update the camera
-    player.update(world, self.cam_width, self.cam_height);
+    player.update(self.cam_width, self.cam_height, alpha);
+    let view = player.camera.interpolated(alpha);
 
     // only update the tiles if the game is paused
     if !player.paused {
       // round cam position to nearest tile
-      let rounded_x = player.x.floor() as i32;
-      let rounded_y = player.y.floor() as i32;
+      let (left, top) = view.top_left();
+      let rounded_x = left.floor() as i32;
+      let rounded_y = top.floor() as i32;
 
       // check if values need update
       if rounded_x != self.prev_x || rounded_y != self.prev_y || self.force_update {
         // if so, update local values
-        let instances = gen_tile_instances(&world, rounded_x, rounded_y, self.cam_width, self.cam_height);
+        let instances = gen_tile_instances(world, &view);
         self.instances = instances;
 
         self.instance_buf = self.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
@@ -539,6 +543,14 @@ impl Render {
     // one should be added to include offscreen tiles
     self.cam_height = new_cam_height + 1;
     self.cam_width = new_cam_width + 1;
+    // the player's sprite is sized in tiles, so it needs remaking for the new number of tiles on screen
+    let (player_vertices, _) = player::player_vertices(self.cam_width, self.cam_height);
+    self.player_vertices = player_vertices;
+    self.player_vertex_buf = self.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+      label: Some("Player Vertex Buffer"),
+      contents: bytemuck::cast_slice(&self.player_vertices),
+      usage: wgpu::BufferUsages::VERTEX
+    });
     // create new swap chain
     if size.width > 0 && size.height > 0 {
       self.config.width = size.width;
@@ -552,9 +564,13 @@ impl Render {
 }
 
 /**
- * Generate instances of tiles
+ * Generate instances of tiles for everything the camera can see
  */
-pub fn gen_tile_instances (world: &Vec<Vec<tiles::TileProperties>>, start_x: i32, start_y: i32, width: i32, height: i32) -> Vec<tiles::TileInstance> {
+pub fn gen_tile_instances (world: &mut world::World, camera: &camera::Camera) -> Vec<tiles::TileInstance> {
+
+  let (left, top) = camera.top_left();
+  let (start_x, start_y) = (left.floor() as i32, top.floor() as i32);
+  let (width, height) = (camera.width, camera.height);
 
   // create a vector to write to
   let mut instances: Vec<tiles::TileInstance> = Vec::new();
@@ -572,7 +588,7 @@ pub fn gen_tile_instances (world: &Vec<Vec<tiles::TileProperties>>, start_x: i32
       let relative_y = (y - start_y) as f32;
 
       // find the type of tile for this instance
-      let tile_type = world[x as usize][y as usize];
+      let tile_type = world.get_tile(x, y);
 
       instances.push(TileInstance {
         x: relative_x, y: relative_y, // the position on the screen it fills
@@ -0,0 +1,67 @@
+use serde::{Serialize, Deserialize};
+use crate::config;
+
+/**
+ * What happens to the player's items when they die
+ */
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeathPenalty {
+  KeepInventory, // nothing is lost
+  DropInventory, // everything is left in a pile where the player died, which they can go back for
+  LoseInventory // everything is gone for good
+}
+
+/**
+ * Settings for how the game plays, as opposed to how the world is generated. Any fields left out of a rules
+ * file use the default
+ */
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct GameRules {
+  pub death_penalty: DeathPenalty,
+  // how much health the player comes back with, as a fraction of their max health
+  pub respawn_health: f32,
+  // how fast the hunger and thirst meters empty, out of 10 per second
+  pub hunger_rate: f32,
+  pub thirst_rate: f32,
+  // how much faster hunger empties while sprinting
+  pub sprint_hunger_rate: f32,
+  // how fast stamina is used up by sprinting and swimming, and comes back while doing neither
+  pub sprint_stamina_rate: f32,
+  pub swim_stamina_rate: f32,
+  pub stamina_recovery_rate: f32,
+  // how much health is lost per second while starving, dying of thirst or too tired to swim
+  pub empty_meter_damage: f32,
+  // how much thirst one drink from fresh water takes away
+  pub drink_amount: f32
+}
+
+impl Default for GameRules {
+  fn default () -> Self {
+    GameRules {
+      death_penalty: DeathPenalty::DropInventory,
+      respawn_health: 1.,
+      hunger_rate: 0.01,
+      thirst_rate: 0.015,
+      sprint_hunger_rate: 0.03,
+      sprint_stamina_rate: 2.,
+      swim_stamina_rate: 0.4,
+      stamina_recovery_rate: 1.,
+      empty_meter_damage: 0.2,
+      drink_amount: 3.
+    }
+  }
+}
+
+impl GameRules {
+
+  pub fn from_yaml (yaml: &str) -> Result<Self, serde_yaml::Error> {
+    serde_yaml::from_str(yaml)
+  }
+
+  // read rules from a file, using the defaults if there isn't one
+  pub fn load_or_default (path: &str) -> Self {
+    config::load_yaml_or_default(path, "the default rules")
+  }
+
+}
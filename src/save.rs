@@ -0,0 +1,400 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use serde::Deserialize;
+use serde_yaml::{Mapping, Value};
+use crate::player;
+use crate::world;
+
+/**
+ * The version of the save format this build of the game writes. Whenever GameData changes in a way that older
+ * saves can't just be read as, by filling in a default, bump this and add a migration for the change
+ */
+pub const SAVE_VERSION: u32 = 1;
+// where worlds are saved, next to the game
+pub const WORLDS_DIR: &str = "worlds";
+
+// each migration upgrades a save from the version at its index to the next one. saves from before the format had
+// a version count as version 0
+type Migration = fn(&mut Mapping) -> Result<(), SaveError>;
+const MIGRATIONS: [Migration; SAVE_VERSION as usize] = [
+  to_version_1
+];
+
+/**
+ * Everything that can go wrong saving or loading a world
+ */
+#[derive(Debug)]
+pub enum SaveError {
+  NotFound { path: PathBuf }, // there's no save there yet
+  NameTaken { name: String }, // there's already a world with that name
+  InvalidName { name: String }, // the name can't be used as a file name
+  Io { path: PathBuf, err: io::Error }, // the file couldn't be read or written, like when the disk is full
+  Parse(serde_yaml::Error),
+  NotASave, // the file is yaml, but not a map of fields like a save is
+  InvalidVersion, // the version isn't a whole number
+  FutureVersion { version: u32 } // made by a newer version of the game, which might have changed anything
+}
+
+impl fmt::Display for SaveError {
+  fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      SaveError::NotFound { path } => write!(f, "there's no save at {}", path.display()),
+      SaveError::NameTaken { name } => write!(f, "there's already a world called {}", name),
+      SaveError::InvalidName { name } => write!(f, "{:?} can't be used as a world name", name),
+      SaveError::Io { path, err } => write!(f, "couldn't read or write {}: {}", path.display(), err),
+      SaveError::Parse(err) => write!(f, "couldn't read save: {}", err),
+      SaveError::NotASave => write!(f, "the file isn't a save"),
+      SaveError::InvalidVersion => write!(f, "the save's version isn't a whole number"),
+      SaveError::FutureVersion { version } => write!(f, "the save is from a newer version of the game (save version {}, this version can read up to {})", version, SAVE_VERSION)
+    }
+  }
+}
+
+impl std::error::Error for SaveError {}
+
+/**
+ * What was in a save that this version of the game doesn't have anymore, like tiles taken out of content.yaml.
+ * These are left out, and the rest of the save still loads
+ */
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Skipped {
+  // ids, each only once
+  pub tiles: Vec<u16>, // unknown tiles
+  pub items: Vec<u16> // items in the inventory that are unknown or don't fit in it
+}
+
+impl Skipped {
+  pub fn is_empty (&self) -> bool {
+    self.tiles.is_empty() && self.items.is_empty()
+  }
+}
+
+impl fmt::Display for Skipped {
+  fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let list = |ids: &[u16]| ids.iter().map(|id| id.to_string()).collect::<Vec<String>>().join(", ");
+    let mut parts: Vec<String> = Vec::new();
+    if !self.tiles.is_empty() {
+      parts.push(format!("unknown tiles {}", list(&self.tiles)));
+    }
+    if !self.items.is_empty() {
+      parts.push(format!("items {}", list(&self.items)));
+    }
+    write!(f, "left out {}", parts.join(" and "))
+  }
+}
+
+impl From<serde_yaml::Error> for SaveError {
+  fn from (err: serde_yaml::Error) -> Self {
+    SaveError::Parse(err)
+  }
+}
+
+fn key (name: &str) -> Value {
+  Value::String(String::from(name))
+}
+
+// which version a save is, with saves that don't say being from before there were versions
+pub fn version_of (save: &Mapping) -> Result<u32, SaveError> {
+  match save.get(&key("version")) {
+    None => Ok(0),
+    Some(version) => version.as_u64().and_then(|version| u32::try_from(version).ok()).ok_or(SaveError::InvalidVersion)
+  }
+}
+
+/**
+ * Read a save made by any version of the game up to this one, upgrading it one version at a time until it's in
+ * the current format
+ */
+pub fn from_yaml (yaml: &str) -> Result<player::GameData, SaveError> {
+  let mut save = match serde_yaml::from_str(yaml)? {
+    Value::Mapping(save) => save,
+    _ => return Err(SaveError::NotASave)
+  };
+  upgrade(&mut save)?;
+  Ok(serde_yaml::from_value(Value::Mapping(save))?)
+}
+
+// run every migration the save is missing, refusing saves from newer versions
+fn upgrade (save: &mut Mapping) -> Result<(), SaveError> {
+  let version = version_of(save)?;
+  if version > SAVE_VERSION {
+    return Err(SaveError::FutureVersion { version });
+  }
+  for (from, migration) in MIGRATIONS.iter().enumerate().skip(version as usize) {
+    migration(save)?;
+    save.insert(key("version"), Value::from(from as u64 + 1));
+  }
+  Ok(())
+}
+
+/**
+ * The few fields of a save the world list shows. Reading only these skips building the chunks, piles and
+ * everything else, which is most of a save
+ */
+#[derive(Deserialize)]
+struct SaveHeader {
+  version: Option<Value>,
+  seed: u32,
+  time: f32
+}
+
+// read the seed and play time of the save at a path, upgraded the same way a whole save would be
+fn read_header (path: &Path) -> Result<SaveHeader, SaveError> {
+  let yaml = fs::read_to_string(path).map_err(|err| io_error(path, err))?;
+  let header: SaveHeader = serde_yaml::from_str(&yaml)?;
+  let mut save = Mapping::new();
+  if let Some(version) = header.version {
+    save.insert(key("version"), version);
+  }
+  save.insert(key("seed"), Value::from(header.seed));
+  save.insert(key("time"), Value::from(header.time));
+  upgrade(&mut save)?;
+  Ok(serde_yaml::from_value(Value::Mapping(save))?)
+}
+
+pub fn to_yaml (gamedata: &player::GameData) -> Result<String, SaveError> {
+  Ok(serde_yaml::to_string(gamedata)?)
+}
+
+// where the save for a world with a name goes
+pub fn world_path (dir: &Path, name: &str) -> PathBuf {
+  dir.join(format!("{}.yaml", name))
+}
+
+// an error from reading or writing a file, which is only a missing save if the file wasn't there
+fn io_error (path: &Path, err: io::Error) -> SaveError {
+  match err.kind() {
+    io::ErrorKind::NotFound => SaveError::NotFound { path: path.to_path_buf() },
+    _ => SaveError::Io { path: path.to_path_buf(), err }
+  }
+}
+
+// read and upgrade the save at a path
+pub fn read (path: &Path) -> Result<player::GameData, SaveError> {
+  let yaml = fs::read_to_string(path).map_err(|err| io_error(path, err))?;
+  from_yaml(&yaml)
+}
+
+/**
+ * Write a save to a path. It's written to a temporary file next to it first and then moved into place, so if the
+ * game stops halfway through saving, whatever was saved there before is still whole
+ */
+pub fn write (path: &Path, gamedata: &player::GameData) -> Result<(), SaveError> {
+  let yaml = to_yaml(gamedata)?;
+  let io_error = |err| SaveError::Io { path: path.to_path_buf(), err };
+  if let Some(dir) = path.parent() {
+    fs::create_dir_all(dir).map_err(io_error)?;
+  }
+  let temp_path = path.with_extension("yaml.tmp");
+  let mut file = fs::File::create(&temp_path).map_err(io_error)?;
+  let written = file.write_all(yaml.as_bytes())
+    // make sure it's actually on the disk before it replaces the old save
+    .and_then(|_| file.sync_all())
+    .and_then(|_| {
+      drop(file);
+      fs::rename(&temp_path, path)
+    });
+  // don't leave half a save lying around if it didn't work
+  if written.is_err() {
+    let _ = fs::remove_file(&temp_path);
+  }
+  written.map_err(io_error)
+}
+
+/**
+ * What the world list shows about a saved world, without keeping the whole save around
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct WorldInfo {
+  pub name: String,
+  pub last_played: Option<Duration>, // how long before the list was made it was last saved, if the file system keeps track
+  pub seed: u32,
+  pub play_time: f32, // seconds
+  pub problem: Option<String> // why the save couldn't be read, in which case the seed and play time are 0
+}
+
+impl WorldInfo {
+
+  // the seed, play time and how long ago it was played, written out for the world list
+  pub fn describe (&self) -> String {
+    if let Some(problem) = &self.problem {
+      return format!("Can't be loaded: {}", problem);
+    }
+    let minutes = (self.play_time / 60.) as u64;
+    let play_time = if minutes < 60 { format!("{}m", minutes) } else { format!("{}h {}m", minutes / 60, minutes % 60) };
+    let last_played = match self.last_played {
+      Some(ago) if ago.as_secs() < 60 => String::from("just now"),
+      Some(ago) if ago.as_secs() < 60 * 60 => format!("{}m ago", ago.as_secs() / 60),
+      Some(ago) if ago.as_secs() < 24 * 60 * 60 => format!("{}h ago", ago.as_secs() / (60 * 60)),
+      Some(ago) => format!("{} days ago", ago.as_secs() / (24 * 60 * 60)),
+      None => String::from("some time")
+    };
+    format!("Seed {}  Played {}  Last played {}", self.seed, play_time, last_played)
+  }
+
+}
+
+/**
+ * Every world saved in one directory, one file each, named after the world. The game keeps its worlds in
+ * WORLDS_DIR, but any directory works
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct SaveIndex {
+  pub dir: PathBuf
+}
+
+impl Default for SaveIndex {
+  fn default () -> Self {
+    SaveIndex::new(WORLDS_DIR)
+  }
+}
+
+impl SaveIndex {
+
+  pub fn new (dir: impl Into<PathBuf>) -> Self {
+    SaveIndex { dir: dir.into() }
+  }
+
+  pub fn path (&self, name: &str) -> PathBuf {
+    world_path(&self.dir, name)
+  }
+
+  pub fn exists (&self, name: &str) -> bool {
+    self.path(name).is_file()
+  }
+
+  /**
+   * Every world in the directory, most recently played first. Worlds whose saves can't be read are still listed,
+   * so they can be renamed or deleted
+   */
+  pub fn list (&self) -> Result<Vec<WorldInfo>, SaveError> {
+    let entries = match fs::read_dir(&self.dir) {
+      Ok(entries) => entries,
+      // nothing's been saved yet
+      Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+      Err(err) => return Err(SaveError::Io { path: self.dir.clone(), err })
+    };
+    let mut worlds: Vec<WorldInfo> = Vec::new();
+    for entry in entries {
+      let path = entry.map_err(|err| SaveError::Io { path: self.dir.clone(), err })?.path();
+      // leftover temporary files from a save that didn't finish end in .tmp, so they're skipped here too
+      let name = match (path.extension().and_then(|extension| extension.to_str()), path.file_stem().and_then(|stem| stem.to_str())) {
+        (Some("yaml"), Some(name)) if path.is_file() => String::from(name),
+        _ => continue
+      };
+      // worked out now rather than when it's shown, since the time isn't always available (like on the web)
+      let last_played = fs::metadata(&path).and_then(|metadata| metadata.modified()).ok()
+        .map(|modified| modified.elapsed().unwrap_or_default());
+      worlds.push(match read_header(&path) {
+        Ok(header) => WorldInfo { name, last_played, seed: header.seed, play_time: header.time, problem: None },
+        Err(err) => WorldInfo { name, last_played, seed: 0, play_time: 0., problem: Some(err.to_string()) }
+      });
+    }
+    // most recently played first, with worlds the file system doesn't know the time for at the end
+    worlds.sort_by(|a, b| (a.last_played.is_none(), a.last_played).cmp(&(b.last_played.is_none(), b.last_played)).then_with(|| a.name.cmp(&b.name)));
+    Ok(worlds)
+  }
+
+  pub fn read (&self, name: &str) -> Result<player::GameData, SaveError> {
+    read(&self.path(name))
+  }
+
+  pub fn write (&self, name: &str, gamedata: &player::GameData) -> Result<(), SaveError> {
+    SaveIndex::check_name(name)?;
+    write(&self.path(name), gamedata)
+  }
+
+  pub fn rename (&self, name: &str, new_name: &str) -> Result<(), SaveError> {
+    let (path, new_path) = self.move_paths(name, new_name)?;
+    fs::rename(&path, new_path).map_err(|err| io_error(&path, err))
+  }
+
+  // copy a world to a new name, leaving the old one where it is
+  pub fn duplicate (&self, name: &str, new_name: &str) -> Result<(), SaveError> {
+    let (path, new_path) = self.move_paths(name, new_name)?;
+    fs::copy(&path, new_path).map(|_| ()).map_err(|err| io_error(&path, err))
+  }
+
+  pub fn delete (&self, name: &str) -> Result<(), SaveError> {
+    let path = self.path(name);
+    fs::remove_file(&path).map_err(|err| io_error(&path, err))
+  }
+
+  // a name based on another one that no world has yet, like "New Game 2" if there's already a "New Game"
+  pub fn unused_name (&self, base: &str) -> String {
+    (1..).map(|n| if n == 1 { String::from(base) } else { format!("{} {}", base, n) }).find(|name| !self.exists(name)).unwrap()
+  }
+
+  /**
+   * Whether a name can be used for a world. It has to be a single file name that works on every system, and
+   * not start or end with spaces or dots, which some systems quietly drop
+   */
+  pub fn check_name (name: &str) -> Result<(), SaveError> {
+    let invalid = name.trim() != name || name.is_empty() || name.starts_with('.') || name.ends_with('.')
+      || name.chars().any(|character| character.is_control() || "/\\:*?\"<>|".contains(character));
+    if invalid { Err(SaveError::InvalidName { name: String::from(name) }) } else { Ok(()) }
+  }
+
+  // where a world is moving or being copied from and to, as long as there's a world there and not one in the way
+  fn move_paths (&self, name: &str, new_name: &str) -> Result<(PathBuf, PathBuf), SaveError> {
+    SaveIndex::check_name(new_name)?;
+    let path = self.path(name);
+    if !path.is_file() {
+      return Err(SaveError::NotFound { path });
+    }
+    if self.exists(new_name) {
+      return Err(SaveError::NameTaken { name: String::from(new_name) });
+    }
+    Ok((path, self.path(new_name)))
+  }
+
+}
+
+// where version 0 put the player's feet from the top left of the camera, in a window the size the game opens at.
+// the camera was as wide as the window's aspect ratio times 24 tiles, plus one, so this is only right for saves made
+// without resizing the window, and saves didn't say how big it was
+const VERSION_0_FEET_OFFSET: (f32, f32) = (16.5, 14.5);
+// how long a day was in version 0's time, which went up by 0.01 for every window event instead of by the second
+const VERSION_0_DAY_LENGTH: f32 = 36.;
+
+/**
+ * Version 0 saves kept the top left of the camera instead of where the player is, and a time that only decided
+ * the time of day. Some of them also kept every changed tile in one list by world position, instead of grouped by
+ * chunk
+ */
+fn to_version_1 (save: &mut Mapping) -> Result<(), SaveError> {
+  let x = save.remove(&key("x")).and_then(|x| x.as_f64());
+  let y = save.remove(&key("y")).and_then(|y| y.as_f64());
+  if let (None, Some(x), Some(y)) = (save.get(&key("position")), x, y) {
+    save.insert(key("position"), serde_yaml::to_value((x as f32 + VERSION_0_FEET_OFFSET.0, y as f32 + VERSION_0_FEET_OFFSET.1))?);
+  }
+  // stretch the time onto the new day length, so the world comes back at the same time of day. it's counted in
+  // seconds from now on, but there's no telling how long the world was really played for
+  if let Some(time) = save.get(&key("time")).and_then(|time| time.as_f64()) {
+    save.insert(key("time"), Value::from(time as f32 * player::DAY_LENGTH / VERSION_0_DAY_LENGTH));
+  }
+  if let Some(changed_tiles) = save.remove(&key("changed_tiles")) {
+    let changed_tiles: Vec<world::TileChange> = serde_yaml::from_value(changed_tiles)?;
+    let mut chunks: Vec<world::ChunkChanges> = match save.remove(&key("chunks")) {
+      Some(chunks) => serde_yaml::from_value(chunks)?,
+      None => Vec::new()
+    };
+    // group the changes by the chunk they're in, with positions inside the chunk
+    let mut grouped: BTreeMap<(i32, i32), Vec<world::TileChange>> = BTreeMap::new();
+    for change in changed_tiles {
+      let (chunk, (local_x, local_y)) = world::World::chunk_coords(change.x, change.y);
+      grouped.entry(chunk).or_default().push(world::TileChange { x: local_x, y: local_y, ..change });
+    }
+    for ((chunk_x, chunk_y), mut changes) in grouped {
+      changes.sort_by_key(|change| (change.x, change.y));
+      chunks.push(world::ChunkChanges { chunk_x, chunk_y, changes });
+    }
+    save.insert(key("chunks"), serde_yaml::to_value(chunks)?);
+  }
+  Ok(())
+}
@@ -8,7 +8,8 @@ struct Uniforms {
     camera_offset: vec2<f32>,
     is_swimming: i32,
     time: f32,
-    light_intensity: vec3<f32>
+    light_intensity: vec3<f32>,
+    player_offset: vec2<f32>
 };
 @group(1) @binding(0)
 var<uniform> uniforms: Uniforms;
@@ -82,12 +83,12 @@ fn vs_player(
     var out: VertexOutput;
     // if the player is swimming, add a subtle bobbing efffect
     if (uniforms.is_swimming == 1) {
-        out.position = vec4<f32>(position.x, position.y + sin(uniforms.time * 2.) * 0.01, 0.0, 1.0);
+        out.position = vec4<f32>(position.x + uniforms.player_offset.x, position.y + uniforms.player_offset.y + sin(uniforms.time * 2.) * 0.01, 0.0, 1.0);
         // use swimming texture instead of regular texture
         // tileset size is static for now but we'll make it dynamic in the future
         out.tex_coord = vec2<f32>(tex_coord.x + 8. / 128., tex_coord.y);
     } else {
-        out.position = vec4<f32>(position.x, position.y, 0.0, 1.0);
+        out.position = vec4<f32>(position.x + uniforms.player_offset.x, position.y + uniforms.player_offset.y, 0.0, 1.0);
         out.tex_coord = tex_coord;
     }
     out.light_intensity = uniforms.light_intensity;
@@ -1,15 +1,25 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::OnceLock;
 use bytemuck::{Pod, Zeroable};
+use serde::Deserialize;
+use crate::config;
+use crate::render; // for the tileset size constants
 
 #[repr(C)]
 #[derive(Clone, Copy, Debug)]
 pub struct TileProperties {
+  pub id: u16,
   pub ts_coord_x: u32,
   pub ts_coord_y: u32,
   pub animation_frames: u32,
   pub solid: bool,
   pub swimmable: bool,
-  pub slowing: bool, // currently unused
-  pub damaging: bool, // currently unused
+  pub slowing: bool,
+  pub damaging: bool,
+  pub speed_factor: f32, // how much of their speed anyone on the tile keeps, 1 unless it's slowing
+  pub contact_damage: f32, // how much health touching the tile takes away, 0 unless it's damaging
+  pub collision: CollisionBox, // the part of the tile that blocks movement, if it's solid
 
   // properties for a bigger tile (like trees):
   pub width: u32, // how many tiles the object should take up
@@ -41,167 +51,589 @@ pub struct TileInstance {
 }
 
 #[repr(C)]
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Deserialize)]
 pub enum ItemTypes {
   Weapon,
   Tool,
-  Resource
+  Resource,
+  Food
+}
+
+/**
+ * The kinds of tools, which each make harvesting certain tiles faster
+ */
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+pub enum ToolKind {
+  Axe,
+  Pickaxe,
+  Shovel,
+  Sword
+}
+
+/**
+ * What an item does when it's used as a tool
+ */
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub struct ToolDefinition {
+  pub kind: ToolKind,
+  // how much harvesting progress each hit makes on tiles meant for this kind of tool
+  #[serde(default = "one")]
+  pub power: u32,
+  // how many uses the tool has before it breaks
+  pub durability: u32
+}
+
+/**
+ * What eating or drinking an item restores
+ */
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub struct FoodDefinition {
+  #[serde(default)]
+  pub hunger: f32,
+  #[serde(default)]
+  pub thirst: f32
+}
+
+/**
+ * The part of a tile that blocks movement when it's solid, in tiles from the top left of the tile it's on.
+ * Big objects can go past their own tile, but only within the tiles they cover, like a tree's trunk
+ */
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+pub struct CollisionBox {
+  pub x: f32,
+  pub y: f32,
+  pub width: f32,
+  pub height: f32
+}
+
+impl Default for CollisionBox {
+  // the whole tile
+  fn default () -> Self {
+    CollisionBox { x: 0., y: 0., width: 1., height: 1. }
+  }
 }
 
 #[repr(C)]
 #[derive(Clone, Copy, Debug)]
 pub struct ItemProperties<'a> {
+  pub id: u16,
   pub ts_coord_x: u32,
   pub ts_coord_y: u32,
   pub animation_frames: u32,
   pub name: &'a str,
   pub stackable: bool,
-  pub item_type: ItemTypes
+  pub max_stack: u32, // how many fit in one inventory slot, always 1 for items that aren't stackable
+  pub item_type: ItemTypes,
+  pub tool: Option<ToolDefinition>,
+  pub food: Option<FoodDefinition>
+}
+
+// tiles and items that the game's code refers to by name, so every content file has to define them
+pub const REQUIRED_TILES: [&str; 17] = [
+  "deep_ocean", "ocean", "sand", "grass", "stump", "kelp", "lilypad", "cactus", "tree",
+  "snow", "swamp_grass", "jungle_grass", "river", "ford", "rock", "iron_ore", "item_pile"
+];
+pub const REQUIRED_ITEMS: [&str; 2] = ["stick", "sword"];
+// what a tile id that isn't in the content acts and looks like
+pub const UNKNOWN_TILE: &str = "grass";
+
+fn one () -> u32 { 1 }
+fn default_max_stack () -> u32 { 99 }
+fn default_speed_factor () -> f32 { 0.6 }
+fn default_contact_damage () -> f32 { 1. }
+
+/**
+ * An item and how many of it, for harvest drops and recipe inputs
+ */
+#[derive(Deserialize, Debug, Clone)]
+pub struct ItemCount {
+  pub item: String,
+  #[serde(default = "one")]
+  pub count: u32
+}
+
+/**
+ * What happens to a tile when it's harvested
+ */
+#[derive(Deserialize, Debug, Clone)]
+pub struct HarvestDefinition {
+  // the name of the tile it turns into. if there isn't one, it turns into the natural ground for where it is
+  #[serde(default)]
+  pub becomes: Option<String>,
+  #[serde(default)]
+  pub drops: Vec<ItemCount>,
+  // how many hits it takes to harvest without the right tool
+  #[serde(default = "one")]
+  pub hardness: u32,
+  // the kind of tool that harvests it faster, and whether it can't be harvested at all without one
+  #[serde(default)]
+  pub tool: Option<ToolKind>,
+  #[serde(default)]
+  pub requires_tool: bool
+}
+
+/**
+ * A tile as it's written in a content file
+ */
+#[derive(Deserialize, Debug, Clone)]
+pub struct TileDefinition {
+  pub id: u16,
+  pub name: String,
+  pub display_name: String,
+  pub ts_coord_x: u32,
+  pub ts_coord_y: u32,
+  #[serde(default = "one")]
+  pub animation_frames: u32,
+  #[serde(default)]
+  pub solid: bool,
+  #[serde(default)]
+  pub swimmable: bool,
+  #[serde(default)]
+  pub slowing: bool,
+  #[serde(default = "default_speed_factor")]
+  pub speed_factor: f32,
+  #[serde(default)]
+  pub damaging: bool,
+  #[serde(default = "default_contact_damage")]
+  pub contact_damage: f32,
+  #[serde(default = "one")]
+  pub width: u32,
+  #[serde(default = "one")]
+  pub height: u32,
+  #[serde(default)]
+  pub offset_x: i32,
+  #[serde(default)]
+  pub offset_y: i32,
+  #[serde(default)]
+  pub collision: CollisionBox,
+  #[serde(default)]
+  pub placeable_on_water: bool, // for bridges and docks
+  #[serde(default)]
+  pub respawn_point: bool, // for campfires and beds
+  #[serde(default)]
+  pub fresh_water: bool, // whether the player can drink from the tile
+  #[serde(default)]
+  pub harvest: Option<HarvestDefinition>
+}
+
+impl TileDefinition {
+  pub fn properties (&self) -> TileProperties {
+    TileProperties {
+      id: self.id,
+      ts_coord_x: self.ts_coord_x, ts_coord_y: self.ts_coord_y,
+      animation_frames: self.animation_frames,
+      solid: self.solid, swimmable: self.swimmable, slowing: self.slowing, damaging: self.damaging,
+      speed_factor: if self.slowing { self.speed_factor } else { 1. },
+      contact_damage: if self.damaging { self.contact_damage } else { 0. },
+      collision: self.collision,
+      width: self.width, height: self.height,
+      offset_x: self.offset_x, offset_y: self.offset_y
+    }
+  }
+}
+
+/**
+ * An item as it's written in a content file
+ */
+#[derive(Deserialize, Debug, Clone)]
+pub struct ItemDefinition {
+  pub id: u16,
+  pub name: String,
+  pub display_name: String,
+  pub ts_coord_x: u32,
+  pub ts_coord_y: u32,
+  #[serde(default = "one")]
+  pub animation_frames: u32,
+  #[serde(default)]
+  pub stackable: bool,
+  #[serde(default = "default_max_stack")]
+  pub max_stack: u32,
+  pub item_type: ItemTypes,
+  // the name of the tile this item puts down when building, if it can be placed
+  #[serde(default)]
+  pub places: Option<String>,
+  #[serde(default)]
+  pub tool: Option<ToolDefinition>,
+  #[serde(default)]
+  pub food: Option<FoodDefinition>
+}
+
+impl ItemDefinition {
+  pub fn properties (&self) -> ItemProperties<'_> {
+    ItemProperties {
+      id: self.id,
+      ts_coord_x: self.ts_coord_x, ts_coord_y: self.ts_coord_y,
+      animation_frames: self.animation_frames,
+      name: &self.display_name,
+      stackable: self.stackable,
+      max_stack: if self.stackable { self.max_stack } else { 1 },
+      item_type: self.item_type,
+      tool: self.tool,
+      food: self.food
+    }
+  }
 }
 
 /**
- * Game tile properties
+ * A way of turning some items into another, as it's written in a content file
  */
-pub const DEEP_OCEAN: TileProperties = TileProperties {
-  ts_coord_x: 0,
-  ts_coord_y: 0,
-  animation_frames: 4,
-  solid: false,
-  swimmable: true,
-  slowing: true,
-  damaging: false,
-  width: 1,
-  height: 1,
-  offset_x: 0,
-  offset_y: 0
-};
-pub const OCEAN: TileProperties = TileProperties {
-  ts_coord_x: 1,
-  ts_coord_y: 0,
-  animation_frames: 4,
-  solid: false,
-  swimmable: true,
-  slowing: true,
-  damaging: false,
-  width: 1,
-  height: 1,
-  offset_x: 0,
-  offset_y: 0
-};
-pub const SAND: TileProperties = TileProperties {
-  ts_coord_x: 2,
-  ts_coord_y: 0,
-  animation_frames: 1,
-  solid: false,
-  swimmable: false,
-  slowing: false,
-  damaging: false,
-  width: 1,
-  height: 1,
-  offset_x: 0,
-  offset_y: 0
-};
-pub const GRASS: TileProperties = TileProperties {
-  ts_coord_x: 3,
-  ts_coord_y: 0,
-  animation_frames: 1,
-  solid: false,
-  swimmable: false,
-  slowing: false,
-  damaging: false,
-  width: 1,
-  height: 1,
-  offset_x: 0,
-  offset_y: 0
-};
-pub const STUMP: TileProperties = TileProperties {
-  ts_coord_x: 4,
-  ts_coord_y: 0,
-  animation_frames: 1,
-  solid: true,
-  swimmable: false,
-  slowing: false,
-  damaging: false,
-  width: 1,
-  height: 1,
-  offset_x: 0,
-  offset_y: 0
-};
-pub const KELP: TileProperties = TileProperties {
-  ts_coord_x: 5,
-  ts_coord_y: 0,
-  animation_frames: 4,
-  solid: false,
-  swimmable: true,
-  slowing: true,
-  damaging: false,
-  width: 1,
-  height: 1,
-  offset_x: 0,
-  offset_y: 0
-};
-pub const LILYPAD: TileProperties = TileProperties {
-  ts_coord_x: 6,
-  ts_coord_y: 0,
-  animation_frames: 4,
-  solid: false,
-  swimmable: false,
-  slowing: false,
-  damaging: false,
-  width: 1,
-  height: 1,
-  offset_x: 0,
-  offset_y: 0
-};
-pub const CACTUS: TileProperties = TileProperties {
-  ts_coord_x: 7,
-  ts_coord_y: 0,
-  animation_frames: 1,
-  solid: true,
-  swimmable: false,
-  slowing: false,
-  damaging: true, // not yet implemented
-  width: 1,
-  height: 1,
-  offset_x: 0,
-  offset_y: 0
-};
+#[derive(Deserialize, Debug, Clone)]
+pub struct RecipeDefinition {
+  pub output: String,
+  #[serde(default = "one")]
+  pub count: u32,
+  pub inputs: Vec<ItemCount>,
+  // the name of a tile that has to be near the player to craft this, like a workbench
+  #[serde(default)]
+  pub station: Option<String>
+}
+
+#[derive(Deserialize)]
+struct ContentFile {
+  #[serde(default)]
+  tiles: Vec<TileDefinition>,
+  #[serde(default)]
+  items: Vec<ItemDefinition>,
+  #[serde(default)]
+  recipes: Vec<RecipeDefinition>
+}
 
 /**
- * Big objects
+ * Everything that can go wrong loading a content file
  */
-pub const TREE: TileProperties = TileProperties {
-  ts_coord_x: 8,
-  ts_coord_y: 0,
-  animation_frames: 1,
-  solid: true,
-  swimmable: false,
-  slowing: false,
-  damaging: false,
-  width: 3, // because the tree takes up multiple tiles, it needs a width, height, and texture offset
-  height: 4,
-  offset_x: -1,
-  offset_y: -3
-};
+#[derive(Debug)]
+pub enum RegistryError {
+  Parse(serde_yaml::Error),
+  DuplicateId { kind: &'static str, id: u16 },
+  DuplicateName { kind: &'static str, name: String },
+  EmptyName { kind: &'static str, id: u16 },
+  ZeroAnimationFrames { kind: &'static str, name: String },
+  ZeroSize { name: String },
+  OutsideTileset { kind: &'static str, name: String },
+  Missing { kind: &'static str, name: String },
+  UnknownTile { used_by: String, name: String },
+  UnknownItem { used_by: String, name: String },
+  ZeroCount { used_by: String, item: String },
+  ZeroMaxStack { name: String },
+  NoInputs { used_by: String },
+  ZeroHardness { name: String },
+  NoTool { name: String },
+  StackableTool { name: String },
+  ZeroDurability { name: String },
+  InvalidSpeedFactor { name: String },
+  InvalidFood { name: String },
+  InvalidCollision { name: String }
+}
+
+impl fmt::Display for RegistryError {
+  fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      RegistryError::Parse(err) => write!(f, "couldn't parse content file: {}", err),
+      RegistryError::DuplicateId { kind, id } => write!(f, "more than one {} has the id {}", kind, id),
+      RegistryError::DuplicateName { kind, name } => write!(f, "more than one {} is named \"{}\"", kind, name),
+      RegistryError::EmptyName { kind, id } => write!(f, "{} {} has an empty name", kind, id),
+      RegistryError::ZeroAnimationFrames { kind, name } => write!(f, "{} \"{}\" needs at least one animation frame", kind, name),
+      RegistryError::ZeroSize { name } => write!(f, "tile \"{}\" needs a width and height of at least 1", name),
+      RegistryError::OutsideTileset { kind, name } => write!(f, "{} \"{}\" has a texture outside of the tileset", kind, name),
+      RegistryError::Missing { kind, name } => write!(f, "the game needs a {} named \"{}\" but it isn't defined", kind, name),
+      RegistryError::UnknownTile { used_by, name } => write!(f, "\"{}\" refers to a tile named \"{}\" that doesn't exist", used_by, name),
+      RegistryError::UnknownItem { used_by, name } => write!(f, "\"{}\" refers to an item named \"{}\" that doesn't exist", used_by, name),
+      RegistryError::ZeroCount { used_by, item } => write!(f, "\"{}\" has a count of 0 for \"{}\"", used_by, item),
+      RegistryError::ZeroMaxStack { name } => write!(f, "item \"{}\" needs a max_stack of at least 1", name),
+      RegistryError::NoInputs { used_by } => write!(f, "\"{}\" needs at least one input", used_by),
+      RegistryError::ZeroHardness { name } => write!(f, "tile \"{}\" needs a hardness of at least 1", name),
+      RegistryError::NoTool { name } => write!(f, "tile \"{}\" requires a tool but doesn't say which kind", name),
+      RegistryError::StackableTool { name } => write!(f, "item \"{}\" is a tool, so it can't be stackable", name),
+      RegistryError::ZeroDurability { name } => write!(f, "item \"{}\" needs a durability of at least 1", name),
+      RegistryError::InvalidSpeedFactor { name } => write!(f, "tile \"{}\" needs a speed_factor above 0", name),
+      RegistryError::InvalidFood { name } => write!(f, "item \"{}\" is food but doesn't restore any hunger or thirst", name),
+      RegistryError::InvalidCollision { name } => write!(f, "tile \"{}\" has a collision box that's empty or goes outside of the tiles it covers", name)
+    }
+  }
+}
+
+impl std::error::Error for RegistryError {}
 
 /**
- * Item properties
+ * All the tiles and items in the game, looked up by id or by name
  */
+pub struct Registry {
+  tiles: Vec<Option<TileDefinition>>, // indexed by id
+  items: Vec<Option<ItemDefinition>>,
+  tile_names: HashMap<String, u16>,
+  item_names: HashMap<String, u16>,
+  recipes: Vec<RecipeDefinition>,
+  // the biggest width and height of any tile, so we know how far to look for big objects
+  pub max_width: u32,
+  pub max_height: u32
+}
+
+impl Registry {
+
+  pub fn from_yaml (yaml: &str) -> Result<Self, RegistryError> {
+    let content: ContentFile = serde_yaml::from_str(yaml).map_err(RegistryError::Parse)?;
+    // tileset size in tiles, for checking textures
+    let tileset_width = render::TILESET_WIDTH as u32 / 8;
+    let tileset_height = render::TILESET_HEIGHT as u32 / 8;
+
+    let mut registry = Registry {
+      tiles: Vec::new(), items: Vec::new(), tile_names: HashMap::new(), item_names: HashMap::new(), recipes: Vec::new(),
+      max_width: 1, max_height: 1
+    };
+
+    for tile in content.tiles {
+      if tile.name.is_empty() { return Err(RegistryError::EmptyName { kind: "tile", id: tile.id }) }
+      if tile.animation_frames == 0 { return Err(RegistryError::ZeroAnimationFrames { kind: "tile", name: tile.name }) }
+      if tile.width == 0 || tile.height == 0 { return Err(RegistryError::ZeroSize { name: tile.name }) }
+      // a speed factor of 0 would leave the player stuck
+      if tile.speed_factor <= 0. { return Err(RegistryError::InvalidSpeedFactor { name: tile.name }) }
+      // collision is only looked for as far away as the biggest object reaches
+      let collision = &tile.collision;
+      if collision.width <= 0. || collision.height <= 0. || collision.x < tile.offset_x as f32 || collision.y < tile.offset_y as f32
+        || collision.x + collision.width > (tile.offset_x + tile.width as i32) as f32 || collision.y + collision.height > (tile.offset_y + tile.height as i32) as f32 {
+        return Err(RegistryError::InvalidCollision { name: tile.name });
+      }
+      // animation frames are stacked below the first frame
+      if tile.ts_coord_x + tile.width > tileset_width || tile.ts_coord_y + tile.height + tile.animation_frames - 1 > tileset_height {
+        return Err(RegistryError::OutsideTileset { kind: "tile", name: tile.name });
+      }
+      if registry.tile_names.contains_key(&tile.name) { return Err(RegistryError::DuplicateName { kind: "tile", name: tile.name }) }
+      let index = tile.id as usize;
+      if registry.tiles.len() <= index { registry.tiles.resize(index + 1, None); }
+      if registry.tiles[index].is_some() { return Err(RegistryError::DuplicateId { kind: "tile", id: tile.id }) }
+      registry.tile_names.insert(tile.name.clone(), tile.id);
+      registry.max_width = registry.max_width.max(tile.width);
+      registry.max_height = registry.max_height.max(tile.height);
+      registry.tiles[index] = Some(tile);
+    }
+
+    for item in content.items {
+      if item.name.is_empty() { return Err(RegistryError::EmptyName { kind: "item", id: item.id }) }
+      if item.animation_frames == 0 { return Err(RegistryError::ZeroAnimationFrames { kind: "item", name: item.name }) }
+      if item.max_stack == 0 { return Err(RegistryError::ZeroMaxStack { name: item.name }) }
+      if let Some(food) = &item.food {
+        if food.hunger <= 0. && food.thirst <= 0. { return Err(RegistryError::InvalidFood { name: item.name }) }
+      }
+      if let Some(tool) = &item.tool {
+        // durability is kept per item, so tools can't share a slot
+        if item.stackable { return Err(RegistryError::StackableTool { name: item.name }) }
+        if tool.durability == 0 { return Err(RegistryError::ZeroDurability { name: item.name }) }
+      }
+      if item.ts_coord_x >= tileset_width || item.ts_coord_y + item.animation_frames > tileset_height {
+        return Err(RegistryError::OutsideTileset { kind: "item", name: item.name });
+      }
+      if registry.item_names.contains_key(&item.name) { return Err(RegistryError::DuplicateName { kind: "item", name: item.name }) }
+      let index = item.id as usize;
+      if registry.items.len() <= index { registry.items.resize(index + 1, None); }
+      if registry.items[index].is_some() { return Err(RegistryError::DuplicateId { kind: "item", id: item.id }) }
+      registry.item_names.insert(item.name.clone(), item.id);
+      registry.items[index] = Some(item);
+    }
+
+    // now that everything is loaded, check that harvesting only refers to things that exist
+    for tile in registry.tiles.iter().flatten() {
+      if let Some(harvest) = &tile.harvest {
+        if harvest.hardness == 0 { return Err(RegistryError::ZeroHardness { name: tile.name.clone() }) }
+        if harvest.requires_tool && harvest.tool.is_none() { return Err(RegistryError::NoTool { name: tile.name.clone() }) }
+        if let Some(becomes) = &harvest.becomes {
+          if !registry.tile_names.contains_key(becomes) { return Err(RegistryError::UnknownTile { used_by: tile.name.clone(), name: becomes.clone() }) }
+        }
+        for drop in &harvest.drops {
+          if !registry.item_names.contains_key(&drop.item) { return Err(RegistryError::UnknownItem { used_by: tile.name.clone(), name: drop.item.clone() }) }
+          if drop.count == 0 { return Err(RegistryError::ZeroCount { used_by: tile.name.clone(), item: drop.item.clone() }) }
+        }
+      }
+    }
+
+    for item in registry.items.iter().flatten() {
+      if let Some(places) = &item.places {
+        if !registry.tile_names.contains_key(places) { return Err(RegistryError::UnknownTile { used_by: item.name.clone(), name: places.clone() }) }
+      }
+    }
+
+    for recipe in content.recipes {
+      let used_by = format!("recipe for {}", recipe.output);
+      if !registry.item_names.contains_key(&recipe.output) { return Err(RegistryError::UnknownItem { used_by, name: recipe.output }) }
+      if recipe.count == 0 { return Err(RegistryError::ZeroCount { used_by, item: recipe.output }) }
+      if recipe.inputs.is_empty() { return Err(RegistryError::NoInputs { used_by }) }
+      for input in &recipe.inputs {
+        if !registry.item_names.contains_key(&input.item) { return Err(RegistryError::UnknownItem { used_by, name: input.item.clone() }) }
+        if input.count == 0 { return Err(RegistryError::ZeroCount { used_by, item: input.item.clone() }) }
+      }
+      if let Some(station) = &recipe.station {
+        if !registry.tile_names.contains_key(station) { return Err(RegistryError::UnknownTile { used_by, name: station.clone() }) }
+      }
+      registry.recipes.push(recipe);
+    }
+
+    // make sure everything the code looks up by name is actually there
+    for name in REQUIRED_TILES {
+      if !registry.tile_names.contains_key(name) { return Err(RegistryError::Missing { kind: "tile", name: String::from(name) }) }
+    }
+    for name in REQUIRED_ITEMS {
+      if !registry.item_names.contains_key(name) { return Err(RegistryError::Missing { kind: "item", name: String::from(name) }) }
+    }
+
+    Ok(registry)
+  }
+
+  // the content that ships with the game
+  pub fn builtin () -> Self {
+    Registry::from_yaml(include_str!("assets/content.yaml")).expect("built in content.yaml is invalid")
+  }
+
+  // load a content file if it exists, otherwise use the built in content
+  pub fn load_or_builtin (path: &str) -> Self {
+    config::load_yaml_or(path, "the built in content", Registry::from_yaml, Registry::builtin)
+  }
+
+  pub fn tile_definition (&self, id: u16) -> Option<&TileDefinition> {
+    self.tiles.get(id as usize).and_then(|tile| tile.as_ref())
+  }
 
-pub const STICK: ItemProperties = ItemProperties {
-  ts_coord_x: 4,
-  ts_coord_y: 1,
-  animation_frames: 1,
-  name: "Stick",
-  stackable: false,
-  item_type: ItemTypes::Resource
-};
-
-pub const SWORD: ItemProperties = ItemProperties {
-  ts_coord_x: 4,
-  ts_coord_y: 2,
-  animation_frames: 1,
-  name: "Sword",
-  stackable: false,
-  item_type: ItemTypes::Weapon
-};
\ No newline at end of file
+  /**
+   * The properties of the tile with an id, for turning the world's tile ids back into tiles. Ids can come from a
+   * save made with different content, so one that isn't defined acts like UNKNOWN_TILE instead of crashing
+   */
+  pub fn tile_properties (&self, id: u16) -> TileProperties {
+    match self.tile_definition(id) {
+      Some(tile) => tile.properties(),
+      None => self.tile(UNKNOWN_TILE)
+    }
+  }
+
+  pub fn tile_id (&self, name: &str) -> Option<u16> {
+    self.tile_names.get(name).copied()
+  }
+
+  // look up a tile that has to exist, like one from REQUIRED_TILES
+  pub fn tile (&self, name: &str) -> TileProperties {
+    let id = self.tile_id(name).unwrap_or_else(|| panic!("no tile named {}", name));
+    self.tile_definition(id).unwrap().properties()
+  }
+
+  pub fn item_definition (&self, id: u16) -> Option<&ItemDefinition> {
+    self.items.get(id as usize).and_then(|item| item.as_ref())
+  }
+
+  pub fn item_id (&self, name: &str) -> Option<u16> {
+    self.item_names.get(name).copied()
+  }
+
+  // every recipe, in the order they're written in the content file
+  pub fn recipes (&self) -> &[RecipeDefinition] {
+    &self.recipes
+  }
+
+  // look up an item that has to exist, like one from REQUIRED_ITEMS
+  pub fn item (&self, name: &str) -> ItemProperties<'_> {
+    let id = self.item_id(name).unwrap_or_else(|| panic!("no item named {}", name));
+    self.item_definition(id).unwrap().properties()
+  }
+
+}
+
+static REGISTRY: OnceLock<Registry> = OnceLock::new();
+
+// set up the registry the game uses. this has to happen before anything is generated, and only once
+pub fn init_registry (registry: Registry) {
+  if REGISTRY.set(registry).is_err() {
+    println!("Registry was already set up, ignoring the new one");
+  }
+}
+
+// the registry the game uses, which is the built in content unless init_registry was called first
+pub fn registry () -> &'static Registry {
+  REGISTRY.get_or_init(Registry::builtin)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  // a content file with a tile for each of a list of names, plus whatever else is added on the end
+  fn content (tiles: &[&str], rest: &str) -> String {
+    let tiles: String = tiles.iter().enumerate()
+      .map(|(id, name)| format!("  - {{ id: {}, name: {}, display_name: {}, ts_coord_x: 0, ts_coord_y: 0 }}\n", id, name, name))
+      .collect();
+    format!("tiles:\n{}{}", tiles, rest)
+  }
+
+  fn error (yaml: &str) -> RegistryError {
+    match Registry::from_yaml(yaml) {
+      Err(err) => err,
+      Ok(_) => panic!("expected an error from:\n{}", yaml)
+    }
+  }
+
+  #[test]
+  fn builtin_content_is_valid () {
+    let registry = Registry::builtin();
+    assert_eq!(registry.tile_id("grass"), Some(3));
+    assert!(registry.recipes().len() > 1);
+  }
+
+  #[test]
+  fn duplicate_ids_are_refused () {
+    let yaml = "tiles:\n  - { id: 1, name: one, display_name: One, ts_coord_x: 0, ts_coord_y: 0 }\n  - { id: 1, name: two, display_name: Two, ts_coord_x: 0, ts_coord_y: 0 }\n";
+    assert!(matches!(error(yaml), RegistryError::DuplicateId { kind: "tile", id: 1 }));
+    let items = "items:\n  - { id: 0, name: a, display_name: A, ts_coord_x: 0, ts_coord_y: 0, item_type: Resource }\n  - { id: 0, name: b, display_name: B, ts_coord_x: 0, ts_coord_y: 0, item_type: Resource }\n";
+    assert!(matches!(error(items), RegistryError::DuplicateId { kind: "item", id: 0 }));
+  }
+
+  #[test]
+  fn duplicate_names_are_refused () {
+    match error(&content(&["grass", "sand", "grass"], "")) {
+      RegistryError::DuplicateName { kind, name } => assert_eq!((kind, name.as_str()), ("tile", "grass")),
+      other => panic!("expected a duplicate name, got {:?}", other)
+    }
+  }
+
+  #[test]
+  fn unknown_references_are_refused () {
+    let drops = "  - { id: 9, name: bush, display_name: Bush, ts_coord_x: 0, ts_coord_y: 0, harvest: { drops: [{ item: berry }] } }\n";
+    match error(&content(&["grass"], drops)) {
+      RegistryError::UnknownItem { used_by, name } => assert_eq!((used_by.as_str(), name.as_str()), ("bush", "berry")),
+      other => panic!("expected an unknown item, got {:?}", other)
+    }
+    let becomes = "  - { id: 9, name: bush, display_name: Bush, ts_coord_x: 0, ts_coord_y: 0, harvest: { becomes: hole } }\n";
+    assert!(matches!(error(&content(&["grass"], becomes)), RegistryError::UnknownTile { .. }));
+    let places = "items:\n  - { id: 0, name: seed, display_name: Seed, ts_coord_x: 0, ts_coord_y: 0, item_type: Resource, places: flower }\n";
+    assert!(matches!(error(&content(&["grass"], places)), RegistryError::UnknownTile { .. }));
+    let recipe = "recipes:\n  - { output: gold, inputs: [{ item: grass }] }\n";
+    assert!(matches!(error(&content(&["grass"], recipe)), RegistryError::UnknownItem { .. }));
+  }
+
+  #[test]
+  fn zero_counts_are_refused () {
+    let drops = "  - { id: 9, name: bush, display_name: Bush, ts_coord_x: 0, ts_coord_y: 0, harvest: { drops: [{ item: stick, count: 0 }] } }\n";
+    let items = "items:\n  - { id: 0, name: stick, display_name: Stick, ts_coord_x: 0, ts_coord_y: 0, item_type: Resource }\n";
+    assert!(matches!(error(&content(&["grass"], &format!("{}{}", drops, items))), RegistryError::ZeroCount { .. }));
+    let recipe = "recipes:\n  - { output: stick, inputs: [{ item: stick, count: 0 }] }\n";
+    match error(&content(&["grass"], &format!("{}{}", items, recipe))) {
+      RegistryError::ZeroCount { used_by, item } => assert_eq!((used_by.as_str(), item.as_str()), ("recipe for stick", "stick")),
+      other => panic!("expected a zero count, got {:?}", other)
+    }
+  }
+
+  #[test]
+  fn content_the_game_needs_has_to_be_there () {
+    match error(&content(&["grass"], "")) {
+      RegistryError::Missing { kind, name } => assert_eq!((kind, name.as_str()), ("tile", "deep_ocean")),
+      other => panic!("expected a missing tile, got {:?}", other)
+    }
+    let items = "items:\n  - { id: 0, name: stick, display_name: Stick, ts_coord_x: 0, ts_coord_y: 0, item_type: Resource }\n";
+    match error(&content(&REQUIRED_TILES, items)) {
+      RegistryError::Missing { kind, name } => assert_eq!((kind, name.as_str()), ("item", "sword")),
+      other => panic!("expected a missing item, got {:?}", other)
+    }
+  }
+
+  #[test]
+  fn unknown_tile_ids_dont_crash () {
+    let registry = Registry::builtin();
+    assert!(registry.tile_definition(5000).is_none());
+    assert_eq!(registry.tile_properties(5000).id, registry.tile(UNKNOWN_TILE).id);
+  }
+
+}
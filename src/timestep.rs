@@ -0,0 +1,111 @@
+use std::time::Duration;
+use instant::Instant; // std's Instant doesn't work on the web
+
+// how many times a second the game is updated, however often it's drawn
+pub const TICKS_PER_SECOND: u32 = 60;
+// the most ticks run in one frame. if the game falls further behind than this it slows down instead of
+// spending longer and longer catching up
+pub const MAX_TICKS_PER_FRAME: u32 = 8;
+
+/**
+ * Works out how many fixed length ticks to run each frame, so the game runs at the same speed no matter the
+ * frame rate. Time that doesn't make up a whole tick is carried over to the next frame, and how far into the
+ * next tick it is gets used to draw things part of the way between ticks
+ */
+pub struct Timestep {
+  pub accumulator: Duration, // time that's passed but hasn't been ticked yet
+  pub last_frame: Instant
+}
+
+impl Default for Timestep {
+  fn default () -> Self {
+    Timestep::new()
+  }
+}
+
+impl Timestep {
+
+  pub fn new () -> Self {
+    Timestep { accumulator: Duration::ZERO, last_frame: Instant::now() }
+  }
+
+  pub fn tick_length () -> Duration {
+    Duration::from_secs(1) / TICKS_PER_SECOND
+  }
+
+  // how many ticks are due since the last frame
+  pub fn advance (&mut self) -> u32 {
+    let now = Instant::now();
+    let elapsed = now - self.last_frame;
+    self.last_frame = now;
+    self.add(elapsed)
+  }
+
+  // add some time that's passed, taking out as many ticks as it makes up
+  pub fn add (&mut self, elapsed: Duration) -> u32 {
+    let tick_length = Timestep::tick_length();
+    self.accumulator += elapsed;
+    let mut ticks = 0;
+    while self.accumulator >= tick_length {
+      if ticks == MAX_TICKS_PER_FRAME {
+        // drop whatever's left rather than carrying a backlog into the next frame
+        self.accumulator = Duration::ZERO;
+        break;
+      }
+      self.accumulator -= tick_length;
+      ticks += 1;
+    }
+    ticks
+  }
+
+  // how far the time is into the next tick, from 0 to 1
+  pub fn alpha (&self) -> f32 {
+    self.accumulator.as_secs_f32() / Timestep::tick_length().as_secs_f32()
+  }
+
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn less_than_a_tick_is_carried_over () {
+    let mut timestep = Timestep::new();
+    let third = Timestep::tick_length() / 3;
+    assert_eq!(timestep.add(third), 0);
+    assert_eq!(timestep.add(third), 0);
+    assert!((timestep.alpha() - 2. / 3.).abs() < 0.001);
+    // the third third makes up a whole tick
+    assert_eq!(timestep.add(Timestep::tick_length() - third * 2), 1);
+    assert_eq!(timestep.accumulator, Duration::ZERO);
+  }
+
+  #[test]
+  fn whole_ticks_come_out_exactly () {
+    let mut timestep = Timestep::new();
+    for ticks in 1..=MAX_TICKS_PER_FRAME {
+      assert_eq!(timestep.add(Timestep::tick_length() * ticks), ticks);
+      assert_eq!(timestep.accumulator, Duration::ZERO);
+    }
+  }
+
+  #[test]
+  fn falling_far_behind_drops_the_backlog () {
+    let mut timestep = Timestep::new();
+    assert_eq!(timestep.add(Duration::from_secs(5)), MAX_TICKS_PER_FRAME);
+    assert_eq!(timestep.accumulator, Duration::ZERO);
+    assert_eq!(timestep.add(Timestep::tick_length() / 2), 0);
+  }
+
+  #[test]
+  fn alpha_stays_below_one () {
+    let mut timestep = Timestep::new();
+    let step = Timestep::tick_length() / 7;
+    for _ in 0..100 {
+      timestep.add(step);
+      assert!((0. ..1.).contains(&timestep.alpha()), "alpha is {}", timestep.alpha());
+    }
+  }
+
+}
@@ -1,5 +1,5 @@
+use crate::inventory;
 use crate::render;
-use crate::tiles;
 
 use std::convert::TryInto;
 
@@ -22,6 +22,57 @@ impl Group {
   
 }
 
+/**
+ * Which button has been picked in a menu by moving through them with a controller or the keyboard, for playing
+ * without a mouse. Buttons and inventory slots count themselves off as they're drawn, so the focus is just a
+ * place in the order they're drawn in
+ */
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Focus {
+  pub index: Option<usize>, // nothing is focused until the focus is first moved
+  pub activate: bool, // whether to click whatever's focused the next time it's drawn
+  pub count: usize // how many things there were to focus on the last time they were drawn
+}
+
+impl Focus {
+
+  pub fn next (&mut self) {
+    self.index = Some(match self.index {
+      Some(index) if self.count > 0 => (index + 1) % self.count,
+      _ => 0
+    });
+  }
+
+  pub fn previous (&mut self) {
+    self.index = Some(match self.index {
+      Some(index) if self.count > 0 => (index + self.count - 1) % self.count,
+      _ => 0
+    });
+  }
+
+  // a copy to count things off with while drawing a frame
+  pub fn begin (&self) -> Focus {
+    Focus { count: 0, ..*self }
+  }
+
+  // count something off, returning whether it's focused and whether it should be clicked
+  pub fn take (&mut self) -> (bool, bool) {
+    let focused = self.index == Some(self.count);
+    self.count += 1;
+    (focused, focused && self.activate)
+  }
+
+  // done drawing, so keep the focus on something that's still there
+  pub fn finish (mut self) -> Focus {
+    self.activate = false;
+    if let Some(index) = self.index {
+      self.index = if self.count == 0 { None } else { Some(index.min(self.count - 1)) };
+    }
+    self
+  }
+
+}
+
 pub struct Button<F> where F: FnMut() {
   pub label: String,
   pub pos: [f32; 2],
@@ -30,9 +81,10 @@ pub struct Button<F> where F: FnMut() {
 
 impl<F> Button<F> where F: FnMut() {
 
-  pub fn gen_vertices (&mut self, mouse_pos: &[f32; 2], mouse_down: bool) -> Vec<render::Vertex> {
+  pub fn gen_vertices (&mut self, mouse_pos: &[f32; 2], mouse_down: bool, focus: &mut Focus) -> Vec<render::Vertex> {
 
     let mut vertices: Vec<render::Vertex> = Vec::new();
+    let (focused, activated) = focus.take();
     // add button texture at specified position
     let start_x = self.pos[0] - TILE_WIDTH * 3.;
     let start_y = self.pos[1] + TILE_HEIGHT / 1.5;
@@ -46,15 +98,16 @@ impl<F> Button<F> where F: FnMut() {
     ];
 
 
-    // check if mouse is over button
-    if start_x <= mouse_pos[0] && mouse_pos[0] <= end_x && start_y >= mouse_pos[1] && mouse_pos[1] >= end_y {
+    // check if mouse is over button, or it's been picked without the mouse
+    let hovered = start_x <= mouse_pos[0] && mouse_pos[0] <= end_x && start_y >= mouse_pos[1] && mouse_pos[1] >= end_y;
+    if hovered || focused {
       // move the tex coords over to hover style
       tex_coords[0][0] += TILE_WIDTH * 4.;
       tex_coords[1][0] += TILE_WIDTH * 4.;
       tex_coords[2][0] += TILE_WIDTH * 4.;
       tex_coords[3][0] += TILE_WIDTH * 4.;
       // check if the button is being clicked
-      if mouse_down {
+      if (hovered && mouse_down) || activated {
         // run the closure
         (self.click)();
       }
@@ -187,34 +240,78 @@ impl Label {
 
 }
 
-pub struct Inventory<'a> {
+/**
+ * A rectangle filled with a single tile's texture, growing up and to the right from its position
+ */
+pub struct Bar {
+  pub pos: [f32; 2],
+  pub width: f32,
+  pub height: f32,
+  pub tex_coords: [f32; 2] // the top left of the tile to fill it with
+}
+
+impl GenVertices for Bar {
+
+  fn gen_vertices (&self) -> Vec<render::Vertex> {
+    let [x, y] = self.pos;
+    let [tex_x, tex_y] = self.tex_coords;
+    vec![
+      render::Vertex { pos: [ x, y + self.height ], tex_coords: [tex_x, tex_y], animation_frames: 1. }, // top left
+      render::Vertex { pos: [ x, y ], tex_coords: [tex_x, tex_y + TILE_HEIGHT], animation_frames: 1. }, // bottom left
+      render::Vertex { pos: [ x + self.width, y ], tex_coords: [tex_x + TILE_WIDTH, tex_y + TILE_HEIGHT], animation_frames: 1. }, // bottom right
+      render::Vertex { pos: [ x, y + self.height ], tex_coords: [tex_x, tex_y], animation_frames: 1. }, // top left
+      render::Vertex { pos: [ x + self.width, y ], tex_coords: [tex_x + TILE_WIDTH, tex_y + TILE_HEIGHT], animation_frames: 1. }, // bottom right
+      render::Vertex { pos: [ x + self.width, y + self.height ], tex_coords: [tex_x + TILE_WIDTH, tex_y], animation_frames: 1. } // top right
+    ]
+  }
+
+}
+
+pub struct Inventory<'a, F> where F: FnMut(usize) {
   pub pos: [f32; 2],
   pub size_x: f32,
   pub size_y: f32,
-  pub inventory: &'a Vec<tiles::ItemProperties<'a>>
+  pub inventory: &'a inventory::Inventory<'a>,
+  pub selected: Option<usize>, // the slot to highlight
+  pub click: F // run with the slot that was clicked on
 }
 
-impl Inventory<'_> {
+impl<F> Inventory<'_, F> where F: FnMut(usize) {
 
-  pub fn gen_vertices (&self, mouse_pos: &[f32; 2]) -> Vec<render::Vertex> {
+  pub fn gen_vertices (&mut self, mouse_pos: &[f32; 2], mouse_down: bool, focus: &mut Focus) -> Vec<render::Vertex> {
     let mut vectors: Vec<render::Vertex> = Vec::new();
     let items_per_row: usize = 8;
     // start position
     let start_x: f32 = 0.8 / (items_per_row as f32 * 3.) - 0.75;
     let start_y: f32 = self.pos[1] as f32;
 
-    for (i, item) in self.inventory.iter().enumerate() {
+    for (i, slot) in self.inventory.slots.iter().enumerate() {
       // find the column that it is in
       let column = i % items_per_row;
       let row = i / items_per_row;
-      // make the background
-      vectors.push(render::Vertex { pos: [ start_x + (column as f32 * TILE_WIDTH * 3.), start_y - (row as f32 * TILE_HEIGHT * 3. ) ], tex_coords: [TILE_WIDTH * 5., TILE_HEIGHT * 4.], animation_frames: 1. }); // top left
-      vectors.push(render::Vertex { pos: [ start_x + (column as f32 * TILE_WIDTH * 3.), start_y - (row as f32 * TILE_HEIGHT * 3. ) + TILE_HEIGHT * 1.5 ], tex_coords: [TILE_WIDTH * 5., TILE_HEIGHT * 5.], animation_frames: 1. }); // bottom left
-      vectors.push(render::Vertex { pos: [ start_x + (column as f32 * TILE_WIDTH * 3.) + TILE_WIDTH * 1.5, start_y - (row as f32 * TILE_HEIGHT * 3. ) + TILE_HEIGHT * 1.5 ], tex_coords: [TILE_WIDTH * 6., TILE_HEIGHT * 5.], animation_frames: 1. }); // bottom right
+      // check if the slot is being clicked
+      let slot_x = start_x + (column as f32 * TILE_WIDTH * 3.);
+      let slot_y = start_y - (row as f32 * TILE_HEIGHT * 3. );
+      let (focused, activated) = focus.take();
+      if activated || (mouse_down && slot_x <= mouse_pos[0] && mouse_pos[0] <= slot_x + TILE_WIDTH * 1.5 && slot_y <= mouse_pos[1] && mouse_pos[1] <= slot_y + TILE_HEIGHT * 1.5) {
+        (self.click)(i);
+      }
+      // make the background, using the button hover colour for the selected slot and the button colour for the focused one
+      let background_x = if self.selected == Some(i) { TILE_WIDTH * 11. } else if focused { TILE_WIDTH * 7. } else { TILE_WIDTH * 5. };
+      vectors.push(render::Vertex { pos: [ start_x + (column as f32 * TILE_WIDTH * 3.), start_y - (row as f32 * TILE_HEIGHT * 3. ) ], tex_coords: [background_x, TILE_HEIGHT * 4.], animation_frames: 1. }); // top left
+      vectors.push(render::Vertex { pos: [ start_x + (column as f32 * TILE_WIDTH * 3.), start_y - (row as f32 * TILE_HEIGHT * 3. ) + TILE_HEIGHT * 1.5 ], tex_coords: [background_x, TILE_HEIGHT * 5.], animation_frames: 1. }); // bottom left
+      vectors.push(render::Vertex { pos: [ start_x + (column as f32 * TILE_WIDTH * 3.) + TILE_WIDTH * 1.5, start_y - (row as f32 * TILE_HEIGHT * 3. ) + TILE_HEIGHT * 1.5 ], tex_coords: [background_x + TILE_WIDTH, TILE_HEIGHT * 5.], animation_frames: 1. }); // bottom right
       // second tri
-      vectors.push(render::Vertex { pos: [ start_x + (column as f32 * TILE_WIDTH * 3.), start_y - (row as f32 * TILE_HEIGHT * 3. ) ], tex_coords: [TILE_WIDTH * 5., TILE_HEIGHT * 4.], animation_frames: 1. }); // top left
-      vectors.push(render::Vertex { pos: [ start_x + (column as f32 * TILE_WIDTH * 3.) + TILE_WIDTH * 1.5, start_y - (row as f32 * TILE_HEIGHT * 3. ) + TILE_HEIGHT * 1.5 ], tex_coords: [TILE_WIDTH * 6., TILE_HEIGHT * 5.], animation_frames: 1. }); // bottom right
-      vectors.push(render::Vertex { pos: [ start_x + (column as f32 * TILE_WIDTH * 3.) + TILE_WIDTH * 1.5, start_y - (row as f32 * TILE_HEIGHT * 3. ) ], tex_coords: [TILE_WIDTH * 5., TILE_HEIGHT * 5.], animation_frames: 1. }); // top right
+      vectors.push(render::Vertex { pos: [ start_x + (column as f32 * TILE_WIDTH * 3.), start_y - (row as f32 * TILE_HEIGHT * 3. ) ], tex_coords: [background_x, TILE_HEIGHT * 4.], animation_frames: 1. }); // top left
+      vectors.push(render::Vertex { pos: [ start_x + (column as f32 * TILE_WIDTH * 3.) + TILE_WIDTH * 1.5, start_y - (row as f32 * TILE_HEIGHT * 3. ) + TILE_HEIGHT * 1.5 ], tex_coords: [background_x + TILE_WIDTH, TILE_HEIGHT * 5.], animation_frames: 1. }); // bottom right
+      vectors.push(render::Vertex { pos: [ start_x + (column as f32 * TILE_WIDTH * 3.) + TILE_WIDTH * 1.5, start_y - (row as f32 * TILE_HEIGHT * 3. ) ], tex_coords: [background_x, TILE_HEIGHT * 5.], animation_frames: 1. }); // top right
+
+      // empty slots only get a background
+      let stack = match slot {
+        Some(stack) => stack,
+        None => continue
+      };
+      let item = stack.item;
 
       // make the icon
       vectors.push(render::Vertex { pos: [ start_x + (column as f32 * TILE_WIDTH * 3.), start_y - (row as f32 * TILE_HEIGHT * 3. ) ], tex_coords: [TILE_WIDTH * item.ts_coord_x as f32, TILE_HEIGHT * (item.ts_coord_y as f32 + 1.)], animation_frames: 1. }); // top left
@@ -229,6 +326,18 @@ impl Inventory<'_> {
       //if (mouse_pos[0] >= start_x + (column as f32 * TILE_WIDTH * 3.)) && (start_x + (column as f32 * TILE_WIDTH * 3.) + TILE_WIDTH >= mouse_pos[0]) && (mouse_pos[1] <= start_y - (row as f32 * TILE_HEIGHT * 3. )) && (start_y - (row as f32 * TILE_HEIGHT * 3. ) + TILE_HEIGHT <= mouse_pos[1]) {
         vectors.append(&mut Label { text: String::from(item.name), size_x: self.size_x / 6., size_y: self.size_y / 6., pos: [start_x + (column as f32 * TILE_WIDTH * 3.) + TILE_WIDTH * 0.5, start_y - (row as f32 * TILE_HEIGHT * 3. ) - TILE_HEIGHT * 0.5 ]}.gen_vertices());
       //}
+
+      // tools get a bar along the bottom of the slot showing how much durability they have left
+      if let (Some(durability), Some(tool)) = (stack.durability, item.tool) {
+        let bar_width = TILE_WIDTH * 1.5 * (durability as f32 / tool.durability as f32).min(1.);
+        vectors.append(&mut Bar { pos: [slot_x, slot_y], width: TILE_WIDTH * 1.5, height: TILE_HEIGHT * 0.2, tex_coords: [TILE_WIDTH * 5., TILE_HEIGHT * 4.] }.gen_vertices());
+        vectors.append(&mut Bar { pos: [slot_x, slot_y], width: bar_width, height: TILE_HEIGHT * 0.2, tex_coords: [TILE_WIDTH * 12., TILE_HEIGHT * 3.] }.gen_vertices());
+      }
+
+      // show how many are in the stack in the bottom right corner
+      if stack.count > 1 {
+        vectors.append(&mut Label { text: stack.count.to_string(), size_x: self.size_x / 6., size_y: self.size_y / 6., pos: [slot_x + TILE_WIDTH * 1.3, slot_y + TILE_HEIGHT * 0.25] }.gen_vertices());
+      }
     }
 
     vectors.iter().cloned().collect()
@@ -0,0 +1,592 @@
+use std::collections::HashMap;
+use serde::{Serialize, Deserialize};
+use crate::inventory;
+use crate::tiles;
+use crate::worldgen;
+
+// how many tiles wide and tall each chunk is
+pub const CHUNK_SIZE: i32 = 32;
+// how many chunks away from the player a chunk can be before it gets unloaded
+pub const UNLOAD_DISTANCE: i32 = 4;
+
+/**
+ * Extra information about a single tile, for the few tiles that need it
+ */
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TileState {
+  pub damage: u8,
+  pub growth: u8, // growth stage, for things like plants
+  pub orientation: u8, // which way the tile is facing, 0-3 clockwise from up
+  // the tile something was built on, which comes back once it's removed. older saves don't have it
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub under: Option<u16>
+}
+
+impl TileState {
+  pub fn is_default (&self) -> bool {
+    *self == TileState::default()
+  }
+}
+
+/**
+ * A tile that's different from what the seed generates, for saving changes to the world
+ */
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TileChange {
+  pub x: i32,
+  pub y: i32,
+  pub id: u16,
+  // most changed tiles don't have any state, so it's only written out when they do
+  #[serde(default, skip_serializing_if = "TileState::is_default")]
+  pub state: TileState
+}
+
+/**
+ * Every tile in one chunk that's different from what the seed generates. The changes' positions are inside the
+ * chunk, counting from its top left, so a save only has to say which chunk they're in once
+ */
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ChunkChanges {
+  pub chunk_x: i32,
+  pub chunk_y: i32,
+  pub changes: Vec<TileChange>
+}
+
+/**
+ * Items left lying in the world, like everything the player was carrying when they died
+ */
+#[derive(Clone, Debug)]
+pub struct ItemPile {
+  pub items: Vec<inventory::ItemStack<'static>>,
+  pub under: u16 // the tile the pile was dropped on, which comes back once it's picked up
+}
+
+/**
+ * An item pile as it's written in a save file
+ */
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct SavedPile {
+  pub x: i32,
+  pub y: i32,
+  pub under: u16,
+  pub items: Vec<inventory::SavedStack>
+}
+
+/**
+ * What happened when the player hit a tile
+ */
+pub enum HitResult {
+  Nothing, // the tile can't be harvested, or not with what the player is holding
+  Damaged, // the tile took a hit but isn't harvested yet
+  Harvested(Vec<inventory::ItemStack<'static>>) // the tile was harvested, dropping these items
+}
+
+/**
+ * A square section of the world, generated all at once from the seed
+ */
+pub struct Chunk {
+  pub tiles: Vec<u16>, // tile ids stored [x][y] flattened, CHUNK_SIZE * CHUNK_SIZE long
+  pub state: HashMap<usize, TileState>, // only tiles with state are stored, by their index in tiles
+  // chunks that have been changed can't just be regenerated, so their changes are kept when they're unloaded
+  pub modified: bool
+}
+
+impl Chunk {
+
+  pub fn generate (chunk_x: i32, chunk_y: i32, hydrology: &mut worldgen::Hydrology) -> Self {
+    let p_map = worldgen::generate_perlin(&hydrology.layers, chunk_x * CHUNK_SIZE, chunk_y * CHUNK_SIZE, CHUNK_SIZE, CHUNK_SIZE);
+    let mut tiles: Vec<u16> = worldgen::elevation_to_tiles(p_map, &hydrology.layers.settings).into_iter().flatten().collect();
+    // carve rivers and lakes into the terrain
+    for (i, tile) in tiles.iter_mut().enumerate() {
+      let x = chunk_x * CHUNK_SIZE + i as i32 / CHUNK_SIZE;
+      let y = chunk_y * CHUNK_SIZE + i as i32 % CHUNK_SIZE;
+      if let Some(water) = hydrology.water_at(x, y) {
+        *tile = water;
+      }
+    }
+    Chunk { tiles, state: HashMap::new(), modified: false }
+  }
+
+  // a chunk that's entirely one tile
+  pub fn filled (id: u16) -> Self {
+    Chunk { tiles: vec![id; (CHUNK_SIZE * CHUNK_SIZE) as usize], state: HashMap::new(), modified: false }
+  }
+
+  // where a tile is in the tiles vector, using coordinates relative to the chunk's top left
+  pub fn index (local_x: i32, local_y: i32) -> usize {
+    (local_x * CHUNK_SIZE + local_y) as usize
+  }
+
+  pub fn get (&self, local_x: i32, local_y: i32) -> u16 {
+    self.tiles[Chunk::index(local_x, local_y)]
+  }
+
+  // put changes with positions inside the chunk back on top of it
+  pub fn apply (&mut self, changes: &[TileChange]) {
+    for change in changes {
+      let index = Chunk::index(change.x, change.y);
+      self.tiles[index] = change.id;
+      if change.state.is_default() {
+        self.state.remove(&index);
+      } else {
+        self.state.insert(index, change.state);
+      }
+    }
+    self.modified = true;
+  }
+
+}
+
+/**
+ * The whole (effectively infinite) world. Chunks are generated the first time a tile inside them is
+ * requested, and dropped again once the player is far enough away, since they can always be regenerated.
+ * Only the changes to a chunk are kept while it isn't loaded, and they're put back when it's generated again
+ */
+pub struct World {
+  pub seed: u32,
+  pub chunks: HashMap<(i32, i32), Chunk>,
+  pub unloaded_changes: HashMap<(i32, i32), Vec<TileChange>>, // by chunk, with positions inside the chunk
+  pub hydrology: worldgen::Hydrology,
+  pub piles: HashMap<(i32, i32), ItemPile>,
+  // if set, every chunk is just this tile instead of being generated, for building maps by hand
+  pub flat: Option<u16>
+}
+
+impl World {
+
+  pub fn new (seed: u32, settings: worldgen::WorldGenSettings) -> Self {
+    World {
+      seed, chunks: HashMap::new(), unloaded_changes: HashMap::new(), hydrology: worldgen::Hydrology::new(seed, settings),
+      piles: HashMap::new(), flat: None
+    }
+  }
+
+  /**
+   * A world that's nothing but one tile, which other tiles can be set on top of. Handy for trying things
+   * out on a map where everything is known, without depending on what a seed generates
+   */
+  pub fn flat (id: u16) -> Self {
+    World { flat: Some(id), ..World::new(0, worldgen::WorldGenSettings::default()) }
+  }
+
+  // generate a chunk the way this world does, without storing it
+  pub fn generate_chunk (&mut self, chunk_x: i32, chunk_y: i32) -> Chunk {
+    match self.flat {
+      Some(id) => Chunk::filled(id),
+      None => Chunk::generate(chunk_x, chunk_y, &mut self.hydrology)
+    }
+  }
+
+  // the settings this world was generated with
+  pub fn settings (&self) -> &worldgen::WorldGenSettings {
+    &self.hydrology.layers.settings
+  }
+
+  // find which chunk a tile is in, and where it is inside of that chunk
+  // div_euclid and rem_euclid are used so that negative coordinates land in the right chunk
+  pub fn chunk_coords (x: i32, y: i32) -> ((i32, i32), (i32, i32)) {
+    (
+      (x.div_euclid(CHUNK_SIZE), y.div_euclid(CHUNK_SIZE)),
+      (x.rem_euclid(CHUNK_SIZE), y.rem_euclid(CHUNK_SIZE))
+    )
+  }
+
+  // get the chunk a tile is in, generating it if it isn't loaded yet
+  pub fn chunk_at (&mut self, x: i32, y: i32) -> &mut Chunk {
+    let ((chunk_x, chunk_y), _) = World::chunk_coords(x, y);
+    if !self.chunks.contains_key(&(chunk_x, chunk_y)) {
+      let mut chunk = self.generate_chunk(chunk_x, chunk_y);
+      if let Some(changes) = self.unloaded_changes.remove(&(chunk_x, chunk_y)) {
+        chunk.apply(&changes);
+      }
+      self.chunks.insert((chunk_x, chunk_y), chunk);
+    }
+    self.chunks.get_mut(&(chunk_x, chunk_y)).unwrap()
+  }
+
+  pub fn get_tile_id (&mut self, x: i32, y: i32) -> u16 {
+    let (_, (local_x, local_y)) = World::chunk_coords(x, y);
+    self.chunk_at(x, y).get(local_x, local_y)
+  }
+
+  // get the properties of the tile at a world position
+  pub fn get_tile (&mut self, x: i32, y: i32) -> tiles::TileProperties {
+    tiles::registry().tile_properties(self.get_tile_id(x, y))
+  }
+
+  // change the tile at a world position. any state the old tile had is removed
+  pub fn set_tile (&mut self, x: i32, y: i32, id: u16) {
+    let (_, (local_x, local_y)) = World::chunk_coords(x, y);
+    let chunk = self.chunk_at(x, y);
+    let index = Chunk::index(local_x, local_y);
+    chunk.tiles[index] = id;
+    chunk.state.remove(&index);
+    chunk.modified = true;
+  }
+
+  pub fn get_state (&mut self, x: i32, y: i32) -> TileState {
+    let (_, (local_x, local_y)) = World::chunk_coords(x, y);
+    self.chunk_at(x, y).state.get(&Chunk::index(local_x, local_y)).copied().unwrap_or_default()
+  }
+
+  // set the state for a tile. default state isn't stored, to keep chunks small
+  pub fn set_state (&mut self, x: i32, y: i32, state: TileState) {
+    let (_, (local_x, local_y)) = World::chunk_coords(x, y);
+    let chunk = self.chunk_at(x, y);
+    let index = Chunk::index(local_x, local_y);
+    if state == TileState::default() {
+      chunk.state.remove(&index);
+    } else {
+      chunk.state.insert(index, state);
+    }
+    chunk.modified = true;
+  }
+
+  // the natural ground at a position, including rivers and lakes, which is what's left behind when something
+  // growing there is removed
+  pub fn ground_tile (&mut self, x: i32, y: i32) -> u16 {
+    if let Some(id) = self.flat {
+      return id;
+    }
+    if let Some(water) = self.hydrology.water_at(x, y) {
+      return water;
+    }
+    tiles::registry().tile(self.hydrology.layers.biome(x, y).properties().ground).id
+  }
+
+  // build a tile at a position, remembering what was there so it comes back when the tile is harvested
+  pub fn place (&mut self, x: i32, y: i32, id: u16) {
+    let under = self.get_tile_id(x, y);
+    self.set_tile(x, y, id);
+    self.set_state(x, y, TileState { under: Some(under), ..TileState::default() });
+  }
+
+  /**
+   * Harvest the tile at a position, turning it into whatever it becomes, or back into what it was built on.
+   * Returns the items it dropped, or None if the tile can't be harvested
+   */
+  pub fn harvest (&mut self, x: i32, y: i32) -> Option<Vec<inventory::ItemStack<'static>>> {
+    let registry = tiles::registry();
+    let harvest = registry.tile_definition(self.get_tile_id(x, y))?.harvest.as_ref()?;
+    let becomes = match (&harvest.becomes, self.get_state(x, y).under) {
+      (Some(name), _) => registry.tile(name).id,
+      (None, Some(under)) => under,
+      (None, None) => self.ground_tile(x, y)
+    };
+    self.set_tile(x, y, becomes);
+    Some(harvest.drops.iter().map(|drop| inventory::ItemStack::new(registry.item(&drop.item), drop.count)).collect())
+  }
+
+  /**
+   * Hit the tile at a position, possibly with a tool. Each hit adds to the tile's damage, counting for the tool's
+   * power if it's the kind the tile is meant for, and once the damage reaches the tile's hardness it's harvested
+   */
+  pub fn hit (&mut self, x: i32, y: i32, tool: Option<tiles::ToolDefinition>) -> HitResult {
+    let harvest = match tiles::registry().tile_definition(self.get_tile_id(x, y)).and_then(|tile| tile.harvest.as_ref()) {
+      Some(harvest) => harvest,
+      None => return HitResult::Nothing
+    };
+    let right_tool = matches!((harvest.tool, tool), (Some(kind), Some(tool)) if kind == tool.kind);
+    if harvest.requires_tool && !right_tool {
+      return HitResult::Nothing;
+    }
+    let power = if right_tool { tool.map_or(1, |tool| tool.power) } else { 1 };
+    let mut state = self.get_state(x, y);
+    let damage = state.damage as u32 + power;
+    if damage < harvest.hardness {
+      state.damage = damage.min(u8::MAX as u32) as u8;
+      self.set_state(x, y, state);
+      return HitResult::Damaged;
+    }
+    match self.harvest(x, y) {
+      Some(drops) => HitResult::Harvested(drops),
+      None => HitResult::Nothing
+    }
+  }
+
+  // every tile a tile with these properties would cover if it was at a position, for big objects like trees
+  pub fn footprint (x: i32, y: i32, tile: &tiles::TileProperties) -> Vec<(i32, i32)> {
+    let mut tiles: Vec<(i32, i32)> = Vec::new();
+    for tile_x in (x + tile.offset_x)..(x + tile.offset_x + tile.width as i32) {
+      for tile_y in (y + tile.offset_y)..(y + tile.offset_y + tile.height as i32) {
+        tiles.push((tile_x, tile_y));
+      }
+    }
+    tiles
+  }
+
+  // whether a position is covered by a big object from a nearby tile
+  pub fn covered_by_object (&mut self, x: i32, y: i32) -> bool {
+    let registry = tiles::registry();
+    let (reach_x, reach_y) = (registry.max_width as i32, registry.max_height as i32);
+    for tile_x in (x - reach_x)..=(x + reach_x) {
+      for tile_y in (y - reach_y)..=(y + reach_y) {
+        let tile = self.get_tile(tile_x, tile_y);
+        if (tile.width > 1 || tile.height > 1) && World::footprint(tile_x, tile_y, &tile).contains(&(x, y)) {
+          return true;
+        }
+      }
+    }
+    false
+  }
+
+  /**
+   * Check whether a tile can be built at a position. Nothing can be built on solid tiles or under big objects,
+   * and only tiles like bridges can be built on water
+   */
+  pub fn can_place (&mut self, x: i32, y: i32, id: u16) -> bool {
+    let definition = match tiles::registry().tile_definition(id) {
+      Some(definition) => definition,
+      None => return false
+    };
+    let tile = definition.properties();
+    for (tile_x, tile_y) in World::footprint(x, y, &tile) {
+      let existing = self.get_tile(tile_x, tile_y);
+      if existing.solid || existing.id == id || (existing.swimmable && !definition.placeable_on_water) {
+        return false;
+      }
+      if self.covered_by_object(tile_x, tile_y) || self.piles.contains_key(&(tile_x, tile_y)) {
+        return false;
+      }
+    }
+    true
+  }
+
+  // every tile in a loaded chunk that isn't what the seed would generate, sorted by position inside the chunk
+  fn chunk_changes (&mut self, chunk_x: i32, chunk_y: i32) -> Vec<TileChange> {
+    // compare against a freshly generated copy of the chunk
+    let generated = self.generate_chunk(chunk_x, chunk_y);
+    let chunk = &self.chunks[&(chunk_x, chunk_y)];
+    let mut changes: Vec<TileChange> = Vec::new();
+    for (i, id) in chunk.tiles.iter().enumerate() {
+      let state = chunk.state.get(&i).copied().unwrap_or_default();
+      if *id != generated.tiles[i] || state != TileState::default() {
+        changes.push(TileChange { x: i as i32 / CHUNK_SIZE, y: i as i32 % CHUNK_SIZE, id: *id, state });
+      }
+    }
+    changes.sort_by_key(|change| (change.x, change.y));
+    changes
+  }
+
+  /**
+   * Every tile that isn't what the seed would generate, grouped by chunk, whether the chunk is loaded or not.
+   * Chunks and the changes in them are sorted by position, so saving the same world twice gives the same file
+   */
+  pub fn changed_chunks (&mut self) -> Vec<ChunkChanges> {
+    let mut chunks: Vec<ChunkChanges> = self.unloaded_changes.iter()
+      .map(|((chunk_x, chunk_y), changes)| ChunkChanges { chunk_x: *chunk_x, chunk_y: *chunk_y, changes: changes.clone() })
+      .collect();
+    let modified: Vec<(i32, i32)> = self.chunks.iter().filter(|(_, chunk)| chunk.modified).map(|(coords, _)| *coords).collect();
+    for (chunk_x, chunk_y) in modified {
+      let changes = self.chunk_changes(chunk_x, chunk_y);
+      // a chunk can be changed and then put back how it was
+      if !changes.is_empty() {
+        chunks.push(ChunkChanges { chunk_x, chunk_y, changes });
+      }
+    }
+    chunks.sort_by_key(|chunk| (chunk.chunk_x, chunk.chunk_y));
+    chunks
+  }
+
+  /**
+   * Put saved chunk changes back into the world. Chunks that are loaded are changed straight away, and the rest
+   * when they're generated. Tiles that no longer exist in the registry are skipped, and their ids returned
+   */
+  pub fn apply_chunk_changes (&mut self, chunks: &[ChunkChanges]) -> Vec<u16> {
+    let mut skipped: Vec<u16> = Vec::new();
+    for chunk in chunks {
+      let (changes, unknown): (Vec<TileChange>, Vec<TileChange>) = chunk.changes.iter()
+        .partition(|change| tiles::registry().tile_definition(change.id).is_some());
+      skipped.extend(unknown.iter().map(|change| change.id));
+      match self.chunks.get_mut(&(chunk.chunk_x, chunk.chunk_y)) {
+        Some(loaded) => loaded.apply(&changes),
+        None => self.unloaded_changes.entry((chunk.chunk_x, chunk.chunk_y)).or_default().extend(changes)
+      }
+    }
+    skipped.sort();
+    skipped.dedup();
+    skipped
+  }
+
+  // leave some items on the ground, adding them to the pile that's already there if there is one
+  pub fn drop_pile (&mut self, x: i32, y: i32, mut items: Vec<inventory::ItemStack<'static>>) {
+    if items.is_empty() {
+      return;
+    }
+    if let Some(pile) = self.piles.get_mut(&(x, y)) {
+      pile.items.append(&mut items);
+      return;
+    }
+    let under = self.get_tile_id(x, y);
+    self.set_tile(x, y, tiles::registry().tile("item_pile").id);
+    self.piles.insert((x, y), ItemPile { items, under });
+  }
+
+  // pick up the pile at a position, putting back the tile it was on
+  pub fn pick_up_pile (&mut self, x: i32, y: i32) -> Option<Vec<inventory::ItemStack<'static>>> {
+    let pile = self.piles.remove(&(x, y))?;
+    self.set_tile(x, y, pile.under);
+    Some(pile.items)
+  }
+
+  // every pile in the world, sorted by position
+  pub fn saved_piles (&self) -> Vec<SavedPile> {
+    let mut piles: Vec<SavedPile> = self.piles.iter().map(|((x, y), pile)| SavedPile {
+      x: *x, y: *y, under: pile.under,
+      items: pile.items.iter().enumerate().map(|(slot, stack)| stack.to_saved(slot)).collect()
+    }).collect();
+    piles.sort_by_key(|pile| (pile.x, pile.y));
+    piles
+  }
+
+  // put saved piles back. their tiles are already in the saved tile changes, so only the items are needed
+  pub fn load_piles (&mut self, piles: &[SavedPile]) {
+    for pile in piles {
+      let items = pile.items.iter().filter_map(inventory::ItemStack::from_saved).collect();
+      self.piles.insert((pile.x, pile.y), ItemPile { items, under: pile.under });
+    }
+  }
+
+  // drop any chunks that are too far from the given tile position, keeping just the changes to modified ones
+  pub fn unload_far_chunks (&mut self, x: i32, y: i32) {
+    let ((centre_x, centre_y), _) = World::chunk_coords(x, y);
+    let far: Vec<(i32, i32)> = self.chunks.keys()
+      .filter(|(chunk_x, chunk_y)| (chunk_x - centre_x).abs() > UNLOAD_DISTANCE || (chunk_y - centre_y).abs() > UNLOAD_DISTANCE)
+      .copied().collect();
+    for (chunk_x, chunk_y) in far {
+      if self.chunks[&(chunk_x, chunk_y)].modified {
+        let changes = self.chunk_changes(chunk_x, chunk_y);
+        if !changes.is_empty() {
+          self.unloaded_changes.insert((chunk_x, chunk_y), changes);
+        }
+      }
+      self.chunks.remove(&(chunk_x, chunk_y));
+    }
+    // keep the water for any region that a loaded chunk could still be in
+    self.hydrology.unload_far_regions(x, y, UNLOAD_DISTANCE * CHUNK_SIZE / worldgen::REGION_SIZE + 1);
+  }
+
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn id (name: &str) -> u16 {
+    tiles::registry().tile(name).id
+  }
+
+  #[test]
+  fn bridges_can_be_taken_back_up () {
+    let mut world = World::flat(id("ocean"));
+    world.place(0, 0, id("bridge"));
+    let drops = world.harvest(0, 0).unwrap();
+    assert_eq!(drops.iter().map(|stack| (stack.item.name, stack.count)).collect::<Vec<_>>(), vec![("Bridge", 1)]);
+    assert_eq!(world.get_tile_id(0, 0), id("ocean"));
+  }
+
+  #[test]
+  fn harvesting_a_built_tile_puts_back_what_it_was_built_on () {
+    let mut world = World::flat(id("sand"));
+    // digging sand leaves dirt, and a floor built there shouldn't bring the sand back
+    world.harvest(0, 0).unwrap();
+    assert_eq!(world.get_tile_id(0, 0), id("dirt"));
+    world.place(0, 0, id("wood_floor"));
+    world.harvest(0, 0).unwrap();
+    assert_eq!(world.get_tile_id(0, 0), id("dirt"));
+    assert_eq!(world.get_state(0, 0), TileState::default());
+  }
+
+  #[test]
+  fn what_a_tile_was_built_on_is_saved () {
+    let mut world = World::flat(id("sand"));
+    world.set_tile(3, -2, id("dirt"));
+    world.place(3, -2, id("wood_wall"));
+    let changes = world.changed_chunks();
+    let mut loaded = World::flat(id("sand"));
+    loaded.apply_chunk_changes(&changes);
+    assert_eq!(loaded.get_tile_id(3, -2), id("wood_wall"));
+    loaded.harvest(3, -2).unwrap();
+    assert_eq!(loaded.get_tile_id(3, -2), id("dirt"));
+  }
+
+  #[test]
+  fn rivers_are_the_ground_where_they_flow () {
+    let mut world = World::new(7, worldgen::WorldGenSettings::default());
+    let (x, y) = (-256..256).flat_map(|x| (-256..256).map(move |y| (x, y)))
+      .find(|(x, y)| world.hydrology.water_at(*x, *y).is_some())
+      .expect("no rivers or lakes near the middle of the world");
+    let water = world.get_tile_id(x, y);
+    assert_eq!(world.ground_tile(x, y), water);
+    world.place(x, y, id("bridge"));
+    world.harvest(x, y).unwrap();
+    assert_eq!(world.get_tile_id(x, y), water);
+  }
+
+  #[test]
+  fn negative_positions_are_in_the_chunk_before_zero () {
+    assert_eq!(World::chunk_coords(0, 0), ((0, 0), (0, 0)));
+    assert_eq!(World::chunk_coords(-1, -1), ((-1, -1), (31, 31)));
+    assert_eq!(World::chunk_coords(-32, 31), ((-1, 0), (0, 31)));
+    assert_eq!(World::chunk_coords(-33, 32), ((-2, 1), (31, 0)));
+    assert_eq!(World::chunk_coords(5, -64), ((0, -2), (5, 0)));
+  }
+
+  #[test]
+  fn far_chunks_are_unloaded () {
+    let mut world = World::flat(id("grass"));
+    let far = (UNLOAD_DISTANCE + 1) * CHUNK_SIZE;
+    world.get_tile_id(0, 0);
+    world.get_tile_id(-far, 0);
+    world.get_tile_id(0, far + CHUNK_SIZE - 1);
+    world.get_tile_id(UNLOAD_DISTANCE * CHUNK_SIZE, -UNLOAD_DISTANCE * CHUNK_SIZE);
+    world.unload_far_chunks(0, 0);
+    let mut loaded: Vec<(i32, i32)> = world.chunks.keys().copied().collect();
+    loaded.sort();
+    assert_eq!(loaded, vec![(0, 0), (UNLOAD_DISTANCE, -UNLOAD_DISTANCE)]);
+  }
+
+  #[test]
+  fn changes_to_unloaded_chunks_are_kept () {
+    let mut world = World::flat(id("grass"));
+    let far = (UNLOAD_DISTANCE + 1) * CHUNK_SIZE;
+    world.place(far, -1, id("wood_floor"));
+    world.set_tile(1, 1, id("sand"));
+    let before = world.changed_chunks();
+    world.unload_far_chunks(0, 0);
+    assert!(!world.chunks.contains_key(&(UNLOAD_DISTANCE + 1, -1)));
+    assert_eq!(world.changed_chunks(), before);
+    assert_eq!(world.get_tile_id(far, -1), id("wood_floor"));
+    assert_eq!(world.get_state(far, -1).under, Some(id("grass")));
+    assert_eq!(world.changed_chunks(), before);
+  }
+
+  #[test]
+  fn loaded_changes_go_to_chunks_that_are_not_generated_yet () {
+    let mut world = World::flat(id("grass"));
+    world.set_tile(-40, 70, id("sand"));
+    world.get_tile_id(0, 0);
+    let changes = world.changed_chunks();
+    let mut loaded = World::flat(id("grass"));
+    loaded.get_tile_id(0, 0);
+    loaded.apply_chunk_changes(&changes);
+    assert_eq!(loaded.chunks.len(), 1);
+    assert_eq!(loaded.changed_chunks(), changes);
+    assert_eq!(loaded.get_tile_id(-40, 70), id("sand"));
+  }
+
+  #[test]
+  fn unknown_tiles_are_skipped_and_returned () {
+    let mut world = World::flat(id("grass"));
+    let change = |x, id| TileChange { x, y: 0, id, state: TileState::default() };
+    let chunks = vec![
+      ChunkChanges { chunk_x: 0, chunk_y: 0, changes: vec![change(0, 900), change(1, id("sand")), change(2, 900)] },
+      ChunkChanges { chunk_x: 1, chunk_y: 0, changes: vec![change(0, 901)] }
+    ];
+    assert_eq!(world.apply_chunk_changes(&chunks), vec![900, 901]);
+    assert_eq!(world.get_tile_id(0, 0), id("grass"));
+    assert_eq!(world.get_tile_id(1, 0), id("sand"));
+    assert_eq!(world.get_tile_id(CHUNK_SIZE, 0), id("grass"));
+  }
+
+}
@@ -1,56 +1,668 @@
+use std::collections::{HashMap, HashSet, VecDeque};
 use noise::{NoiseFn, Perlin, Seedable};
+use serde::{Serialize, Deserialize};
+use crate::config;
 use crate::tiles;
+use crate::world;
+
+// rivers are worked out for square regions of this many tiles at a time
+pub const REGION_SIZE: i32 = 64;
+// the most tiles a river can flow before it dries up
+pub const MAX_RIVER_LENGTH: i32 = 192;
+// how many regions away a river can start and still reach a region
+const REGION_REACH: i32 = MAX_RIVER_LENGTH / REGION_SIZE + 1;
+// how many tiles, counting across and down, water can end up from a river's source: the river itself, a lake at
+// its end, and the widest river spilling one tile over
+const WATER_REACH: i32 = MAX_RIVER_LENGTH + 2 * MAX_LAKE_RADIUS + 2;
+// how many spots in each region are tried as river sources
+const SOURCE_ATTEMPTS: u32 = 12;
+// how high above sea level a spot has to be for a river to start there
+const SOURCE_ELEVATION: f64 = 0.4;
+// lakes fill a basin up to this much higher than its lowest point
+const LAKE_DEPTH: f64 = 0.03;
+const MAX_LAKE_RADIUS: i32 = 8;
+// roughly one in this many narrow river tiles becomes a ford
+const FORD_SPACING: u32 = 24;
+// how far from the origin to look for somewhere to spawn before giving up
+pub const MAX_SPAWN_DISTANCE: i32 = 512;
+// how many tiles around a spawn point also have to be clear
+const SPAWN_CLEARANCE: i32 = 1;
+
+// a list of tile positions, like the tiles a river runs through
+pub type TilePositions = Vec<(i32, i32)>;
+// a river's path from its source, and the lake it ends in if it has one
+pub type River = (TilePositions, TilePositions);
+
+/**
+* Everything that controls how a world is shaped. Any fields left out of a settings file use the default
+*/
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct WorldGenSettings {
+  // elevation is made of several layers (octaves) of noise added together
+  pub octaves: u32,
+  pub lacunarity: f64, // how much smaller each octave is than the last
+  pub persistence: f64, // how much weaker each octave is than the last
+  pub scale: f64, // how many tiles across the biggest octave's features are
+  pub sea_level: f64,
+  pub deep_ocean_depth: f64, // how far below sea level the ocean turns deep
+  pub beach_width: f64, // how far above sea level the beach goes
+  // shifts the climate everywhere, so a world can be mostly hot or mostly wet
+  pub temperature_offset: f64,
+  pub moisture_offset: f64,
+  // multiplies the vegetation noise, so higher means more plants and 0 means none at all
+  pub land_vegetation_density: f64,
+  pub water_vegetation_density: f64
+}
+
+impl Default for WorldGenSettings {
+  fn default () -> Self {
+    WorldGenSettings {
+      octaves: 1,
+      lacunarity: 2.,
+      persistence: 0.5,
+      scale: 16.,
+      sea_level: 0.,
+      deep_ocean_depth: 0.3,
+      beach_width: 0.2,
+      temperature_offset: 0.,
+      moisture_offset: 0.,
+      land_vegetation_density: 1.,
+      water_vegetation_density: 1.
+    }
+  }
+}
+
+impl WorldGenSettings {
+
+  /**
+  * Built in settings for some different styles of world
+  */
+  pub fn preset (name: &str) -> Option<Self> {
+    match name {
+      "default" => Some(WorldGenSettings::default()),
+      // lots of small islands
+      "archipelago" => Some(WorldGenSettings {
+        octaves: 3, scale: 24., sea_level: 0.15, beach_width: 0.1, water_vegetation_density: 1.2,
+        ..WorldGenSettings::default()
+      }),
+      // huge areas of land with long rivers
+      "continent" => Some(WorldGenSettings {
+        octaves: 5, scale: 128., sea_level: -0.2, deep_ocean_depth: 0.2, beach_width: 0.04,
+        ..WorldGenSettings::default()
+      }),
+      // hot and dry almost everywhere
+      "desert" => Some(WorldGenSettings {
+        octaves: 3, scale: 48., sea_level: -0.25, beach_width: 0.05, temperature_offset: 0.5, moisture_offset: -0.4,
+        land_vegetation_density: 0.8,
+        ..WorldGenSettings::default()
+      }),
+      _ => None
+    }
+  }
+
+  pub fn from_yaml (yaml: &str) -> Result<Self, serde_yaml::Error> {
+    serde_yaml::from_str(yaml)
+  }
+
+  // read settings from a file, using the defaults if there isn't one
+  pub fn load_or_default (path: &str) -> Self {
+    config::load_yaml_or_default(path, "the default world settings")
+  }
+
+}
 
 /**
-* Create a map of specified size with perlin noise points
+* All the noise values that decide what a single tile becomes
 */
-pub fn generate_perlin (width: i32, height: i32, seed: u32) -> Vec<Vec<(f64, f64)>> {
+#[derive(Clone, Copy, Debug)]
+pub struct NoisePoint {
+  pub elevation: f64,
+  pub vegetation: f64,
+  pub temperature: f64,
+  pub moisture: f64
+}
+
+/**
+* The noise functions for a seed. Each layer gets its own seed offset so they don't line up with each other
+*/
+pub struct NoiseLayers {
+  pub settings: WorldGenSettings,
+  pub elevation: Perlin,
+  pub vegetation: Perlin,
+  pub temperature: Perlin,
+  pub moisture: Perlin,
+  pub detail: Perlin // small scale noise used to roughen up the borders between biomes
+}
+
+impl NoiseLayers {
+
+  pub fn new (seed: u32, settings: WorldGenSettings) -> Self {
+    NoiseLayers {
+      settings,
+      elevation: Perlin::new().set_seed(seed),
+      vegetation: Perlin::new().set_seed(seed.wrapping_add(1)),
+      temperature: Perlin::new().set_seed(seed.wrapping_add(2)),
+      moisture: Perlin::new().set_seed(seed.wrapping_add(3)),
+      detail: Perlin::new().set_seed(seed.wrapping_add(4))
+    }
+  }
+
+  pub fn elevation (&self, x: i32, y: i32) -> f64 {
+    // add the octaves together, each one smaller in scale and weaker than the last
+    let mut total: f64 = 0.;
+    let mut max_total: f64 = 0.;
+    let mut amplitude: f64 = 1.;
+    let mut frequency: f64 = 1. / self.settings.scale;
+    for _ in 0..self.settings.octaves.max(1) {
+      total += self.elevation.get([x as f64 * frequency, y as f64 * frequency, 0.]) * amplitude;
+      max_total += amplitude;
+      amplitude *= self.settings.persistence;
+      frequency *= self.settings.lacunarity;
+    }
+    // divide so that adding octaves doesn't change the range of values
+    total / max_total
+  }
+
+  pub fn biome (&self, x: i32, y: i32) -> Biome {
+    Biome::from_noise(&self.sample(x, y), &self.settings)
+  }
+
+  pub fn sample (&self, x: i32, y: i32) -> NoisePoint {
+    let elevation = self.elevation(x, y);
+    let (x, y) = (x as f64, y as f64);
+    // climate changes very slowly over the world, with a little bit of detail noise so borders aren't perfectly smooth
+    let detail = self.detail.get([x / 4., y / 4., 0.]) * 0.05;
+    NoisePoint {
+      elevation,
+      vegetation: self.vegetation.get([x / 1.6, y / 1.6, 0.]),
+      temperature: self.temperature.get([x / 160., y / 160., 0.]) + detail,
+      moisture: self.moisture.get([x / 120., y / 120., 0.]) - detail
+    }
+  }
+
+}
+
+/**
+* Create a map of specified size with perlin noise points, starting from a world position
+*/
+pub fn generate_perlin (layers: &NoiseLayers, start_x: i32, start_y: i32, width: i32, height: i32) -> Vec<Vec<NoisePoint>> {
   // create a vector to store world data in
-  let mut world: Vec<Vec<(f64, f64)>> = Vec::new();
-  // make a perlin noise function to read from
-  let p_noise = Perlin::new().set_seed(seed);
-  // noise for vegetation
-  let veg_noise = Perlin::new().set_seed(p_noise.seed() + 1);
-  println!("Seed: {}", p_noise.seed());
+  let mut world: Vec<Vec<NoisePoint>> = Vec::new();
   // loop [x][y] the size specified
-  for x in 0..width {
-    let mut row: Vec<(f64, f64)> = Vec::new();
-    for y in 0..height {
-      row.push((p_noise.get([x as f64 / 16., y as f64 / 16., 0.]), veg_noise.get([x as f64 / 1.6, y as f64 / 1.6, 0.])));
+  for x in start_x..(start_x + width) {
+    let mut row: Vec<NoisePoint> = Vec::new();
+    for y in start_y..(start_y + height) {
+      row.push(layers.sample(x, y));
+    }
+    world.push(row);
+  }
+
+  world
+}
+
+/**
+* The different kinds of area that make up the world
+*/
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Biome {
+  DeepOcean,
+  Ocean,
+  Beach,
+  Desert,
+  Grassland,
+  Forest,
+  Tundra,
+  Swamp,
+  Jungle
+}
+
+/**
+* What a biome is made of
+*/
+pub struct BiomeProperties {
+  // tiles are referred to by their name in the registry
+  pub ground: &'static str,
+  // checked in order, the first entry that the vegetation noise is above gets placed instead of the ground
+  pub vegetation: &'static [(f64, &'static str)]
+}
+
+impl Biome {
+
+  /**
+  * Pick a biome from elevation first (water and coast), then from temperature and moisture
+  */
+  pub fn from_noise (point: &NoisePoint, settings: &WorldGenSettings) -> Self {
+    if point.elevation < settings.sea_level - settings.deep_ocean_depth { return Biome::DeepOcean }
+    if point.elevation < settings.sea_level { return Biome::Ocean }
+    if point.elevation < settings.sea_level + settings.beach_width { return Biome::Beach }
+
+    let temperature = point.temperature + settings.temperature_offset;
+    let moisture = point.moisture + settings.moisture_offset;
+    if temperature < -0.2 {
+      Biome::Tundra
+    } else if temperature > 0.2 {
+      // hot
+      if moisture < -0.1 { Biome::Desert }
+      else if moisture > 0.15 { Biome::Jungle }
+      else { Biome::Grassland }
+    } else {
+      // temperate
+      if moisture < -0.15 { Biome::Grassland }
+      else if moisture > 0.25 { Biome::Swamp }
+      else { Biome::Forest }
+    }
+  }
+
+  pub fn is_water (&self) -> bool {
+    matches!(self, Biome::DeepOcean | Biome::Ocean)
+  }
+
+  pub fn properties (&self) -> BiomeProperties {
+    match self {
+      Biome::DeepOcean => BiomeProperties { ground: "deep_ocean", vegetation: &[(0.5, "kelp")] },
+      Biome::Ocean => BiomeProperties { ground: "ocean", vegetation: &[(0.7, "lilypad")] },
+      Biome::Beach => BiomeProperties { ground: "sand", vegetation: &[] },
+      Biome::Desert => BiomeProperties { ground: "sand", vegetation: &[(0.65, "rock"), (0.55, "cactus")] },
+      Biome::Grassland => BiomeProperties { ground: "grass", vegetation: &[(0.8, "rock"), (0.75, "stump"), (0.65, "tree")] },
+      Biome::Forest => BiomeProperties { ground: "grass", vegetation: &[(0.7, "stump"), (0.4, "tree")] },
+      Biome::Tundra => BiomeProperties { ground: "snow", vegetation: &[(0.8, "iron_ore"), (0.7, "rock"), (0.6, "tree")] },
+      Biome::Swamp => BiomeProperties { ground: "swamp_grass", vegetation: &[(0.7, "stump"), (0.5, "lilypad"), (0.35, "ocean")] },
+      Biome::Jungle => BiomeProperties { ground: "jungle_grass", vegetation: &[(0.3, "tree")] }
     }
-    world.push(row.iter().cloned().collect());
   }
 
-  world.iter().cloned().collect()
 }
 
 /**
-* Convert perlin noise map to game tiles
-* 0: Deep Ocean
-* 1: Ocean
-* 2: Beach
-* 3: Grass
-* 4: Trees
+* Find the biome at a world position, without generating anything else
+*/
+pub fn biome_at (seed: u32, settings: &WorldGenSettings, x: i32, y: i32) -> Biome {
+  NoiseLayers::new(seed, settings.clone()).biome(x, y)
+}
+
+/**
+* Convert perlin noise map to game tile ids, using the biome each point falls in
 */
-pub fn elevation_to_tiles (p_map: Vec<Vec<(f64, f64)>>) -> Vec<Vec<tiles::TileProperties>> {
+pub fn elevation_to_tiles (p_map: Vec<Vec<NoisePoint>>, settings: &WorldGenSettings) -> Vec<Vec<u16>> {
   // map over the map
-  p_map.iter().map(|p_row| p_row.iter().map(|(tile, veg)| {
-    if tile < &-0.3 { 
-      if veg > &0.5 { return tiles::KELP }
-      else { return tiles::DEEP_OCEAN }
+  p_map.iter().map(|p_row| p_row.iter().map(|point| {
+    let biome = Biome::from_noise(point, settings);
+    let density = if biome.is_water() { settings.water_vegetation_density } else { settings.land_vegetation_density };
+    let properties = biome.properties();
+    // use the first bit of vegetation that the noise is high enough for, otherwise just the ground
+    let tile = properties.vegetation.iter()
+      .find(|(threshold, _)| point.vegetation * density > *threshold)
+      .map(|(_, tile)| *tile)
+      .unwrap_or(properties.ground);
+    tiles::registry().tile(tile).id
+  }).collect()).collect()
+}
+
+/**
+* Cheap deterministic hash of a position, so that "random" choices come out the same every time a seed is generated
+*/
+pub fn hash_position (seed: u32, x: i32, y: i32) -> u32 {
+  let mut h = seed ^ (x as u32).wrapping_mul(0x27d4_eb2d) ^ (y as u32).wrapping_mul(0x1656_67b1);
+  h = (h ^ (h >> 15)).wrapping_mul(0x2c1b_3c6d);
+  h = (h ^ (h >> 12)).wrapping_mul(0x297a_2d39);
+  h ^ (h >> 15)
+}
+
+/**
+* Rivers and lakes, traced downhill over the elevation noise. Rivers can flow across chunk borders, so water is
+* worked out a whole region at a time (including rivers that start in nearby regions) and cached here. The rivers
+* starting in each region are cached too, since every region around them needs them
+*/
+pub struct Hydrology {
+  pub seed: u32,
+  pub layers: NoiseLayers,
+  pub regions: HashMap<(i32, i32), HashMap<(i32, i32), u16>>, // tile ids of the water in each region
+  pub rivers: HashMap<(i32, i32), Vec<River>> // the rivers that start in each region
+}
+
+impl Hydrology {
+
+  pub fn new (seed: u32, settings: WorldGenSettings) -> Self {
+    Hydrology { seed, layers: NoiseLayers::new(seed, settings), regions: HashMap::new(), rivers: HashMap::new() }
+  }
+
+  // get the id of the river, ford or lake tile at a world position, if there is one
+  pub fn water_at (&mut self, x: i32, y: i32) -> Option<u16> {
+    let region = (x.div_euclid(REGION_SIZE), y.div_euclid(REGION_SIZE));
+    if !self.regions.contains_key(&region) {
+      let water = self.gen_region(region);
+      self.regions.insert(region, water);
     }
-    else if tile < &0. { 
-      if veg > &0.7 { return tiles::LILYPAD }
-      else { return tiles::OCEAN }
+    self.regions[&region].get(&(x, y)).copied()
+  }
+
+  // drop cached regions more than `distance` regions away from a tile position
+  pub fn unload_far_regions (&mut self, x: i32, y: i32, distance: i32) {
+    let (centre_x, centre_y) = (x.div_euclid(REGION_SIZE), y.div_euclid(REGION_SIZE));
+    self.regions.retain(|(region_x, region_y), _| {
+      (region_x - centre_x).abs() <= distance && (region_y - centre_y).abs() <= distance
+    });
+    // rivers are needed a little further out, by the regions at the edge
+    self.rivers.retain(|(region_x, region_y), _| {
+      (region_x - centre_x).abs() <= distance + REGION_REACH && (region_y - centre_y).abs() <= distance + REGION_REACH
+    });
+  }
+
+  // the spots in a region that rivers start from
+  pub fn sources (&self, region_x: i32, region_y: i32) -> Vec<(i32, i32)> {
+    (0..SOURCE_ATTEMPTS).map(|attempt| {
+      let hash = hash_position(self.seed.wrapping_add(attempt), region_x, region_y);
+      (
+        region_x * REGION_SIZE + (hash % REGION_SIZE as u32) as i32,
+        region_y * REGION_SIZE + ((hash >> 16) % REGION_SIZE as u32) as i32
+      )
+    }).filter(|(x, y)| self.layers.elevation(*x, *y) > self.layers.settings.sea_level + SOURCE_ELEVATION).collect()
+  }
+
+  /**
+  * Follow the steepest way down from a source until reaching the ocean. If the river gets stuck in a
+  * basin, or runs out of length before reaching the ocean, it ends in a lake. Returns the river's path and the
+  * lake's tiles
+  */
+  pub fn trace_river (&self, start_x: i32, start_y: i32) -> River {
+    let mut path: Vec<(i32, i32)> = Vec::new();
+    let (mut x, mut y) = (start_x, start_y);
+    let mut elevation = self.layers.elevation(x, y);
+
+    for _ in 0..MAX_RIVER_LENGTH {
+      // reaching the ocean is the end of the river
+      if elevation < self.layers.settings.sea_level {
+        return (path, Vec::new());
+      }
+      path.push((x, y));
+      // find the lowest neighbour to flow towards
+      let (next_x, next_y, next_elevation) = [(1, 0), (-1, 0), (0, 1), (0, -1)].iter()
+        .map(|(dx, dy)| (x + dx, y + dy, self.layers.elevation(x + dx, y + dy)))
+        .fold((x, y, elevation), |lowest, next| if next.2 < lowest.2 { next } else { lowest });
+      // nowhere lower to go, so the water pools up
+      if next_elevation >= elevation {
+        return (path, self.fill_lake(x, y, elevation));
+      }
+      x = next_x;
+      y = next_y;
+      elevation = next_elevation;
     }
-    else if tile < &0.2 { 
-      if veg > &0.7 { return tiles::CACTUS }
-      else { return tiles::SAND }
+
+    // too far from the sea, so it pools up where it dries out
+    (path, self.fill_lake(x, y, elevation))
+  }
+
+  // flood fill a basin outwards from its lowest point
+  fn fill_lake (&self, x: i32, y: i32, elevation: f64) -> Vec<(i32, i32)> {
+    let mut lake: Vec<(i32, i32)> = Vec::new();
+    let mut visited: HashSet<(i32, i32)> = HashSet::new();
+    let mut queue: VecDeque<(i32, i32)> = VecDeque::new();
+    visited.insert((x, y));
+    queue.push_back((x, y));
+
+    while let Some((tile_x, tile_y)) = queue.pop_front() {
+      lake.push((tile_x, tile_y));
+      for (dx, dy) in [(1, 0), (-1, 0), (0, 1), (0, -1)] {
+        let next = (tile_x + dx, tile_y + dy);
+        if (next.0 - x).abs() > MAX_LAKE_RADIUS || (next.1 - y).abs() > MAX_LAKE_RADIUS || visited.contains(&next) { continue; }
+        visited.insert(next);
+        let next_elevation = self.layers.elevation(next.0, next.1);
+        if next_elevation >= self.layers.settings.sea_level && next_elevation < elevation + LAKE_DEPTH {
+          queue.push_back(next);
+        }
+      }
     }
-    else { 
-      if veg > &0.7 { return tiles::STUMP }
-      else if veg > &0.5 { return tiles::TREE }
-      else { return tiles::GRASS }
+
+    lake
+  }
+
+  // the rivers that start in a region, traced the first time they're needed
+  pub fn rivers_from (&mut self, region: (i32, i32)) -> &[River] {
+    if !self.rivers.contains_key(&region) {
+      let rivers = self.sources(region.0, region.1).into_iter().map(|(x, y)| self.trace_river(x, y)).collect();
+      self.rivers.insert(region, rivers);
     }
-  }).collect()).collect()
-}
\ No newline at end of file
+    &self.rivers[&region]
+  }
+
+  // work out all the water tiles inside of a region
+  pub fn gen_region (&mut self, (region_x, region_y): (i32, i32)) -> HashMap<(i32, i32), u16> {
+    // how much water flows through each river tile. every river adds how far it has travelled so far,
+    // so rivers get bigger further from their source and where they join together
+    let mut flows: HashMap<(i32, i32), u32> = HashMap::new();
+    let mut lakes: Vec<(i32, i32)> = Vec::new();
+    // the fewest tiles across or down between two regions some number of regions apart
+    let gap = |regions: i32| (regions.abs() - 1).max(0) * REGION_SIZE + regions.abs().min(1);
+
+    for source_region_x in (region_x - REGION_REACH)..=(region_x + REGION_REACH) {
+      for source_region_y in (region_y - REGION_REACH)..=(region_y + REGION_REACH) {
+        // rivers from the far corners can't reach this region
+        if gap(source_region_x - region_x) + gap(source_region_y - region_y) > WATER_REACH { continue; }
+        for (path, lake) in self.rivers_from((source_region_x, source_region_y)) {
+          for (distance, tile) in path.iter().enumerate() {
+            *flows.entry(*tile).or_insert(0) += distance as u32 + 1;
+          }
+          lakes.extend_from_slice(lake);
+        }
+      }
+    }
+
+    let in_region = |x: i32, y: i32| x.div_euclid(REGION_SIZE) == region_x && y.div_euclid(REGION_SIZE) == region_y;
+    let mut water: HashMap<(i32, i32), u16> = HashMap::new();
+    let river = tiles::registry().tile("river").id;
+    let ford = tiles::registry().tile("ford").id;
+
+    for ((x, y), flow) in flows {
+      // wider rivers cover the tiles to the right and below as well
+      let width: i32 = if flow < 30 { 1 } else if flow < 80 { 2 } else { 3 };
+      let is_ford = width < 3 && hash_position(self.seed, x, y).is_multiple_of(FORD_SPACING);
+      let start = if width == 3 { -1 } else { 0 };
+      for tile_x in (x + start)..(x + start + width) {
+        for tile_y in (y + start)..(y + start + width) {
+          // rivers don't spill out into the ocean
+          if !in_region(tile_x, tile_y) || self.layers.elevation(tile_x, tile_y) < self.layers.settings.sea_level { continue; }
+          // fords always win over regular river tiles so that the crossing isn't cut in half
+          if is_ford {
+            water.insert((tile_x, tile_y), ford);
+          } else {
+            water.entry((tile_x, tile_y)).or_insert(river);
+          }
+        }
+      }
+    }
+
+    for (x, y) in lakes {
+      if in_region(x, y) {
+        water.entry((x, y)).or_insert(river);
+      }
+    }
+
+    water
+  }
+
+}
+
+/**
+* Search outwards from the origin one square ring at a time for somewhere dry and open to put the player.
+* If nowhere is found the origin is used anyway
+*/
+pub fn find_spawn (world: &mut world::World) -> (i32, i32) {
+  find_safe_near(world, 0, 0, MAX_SPAWN_DISTANCE).unwrap_or((0, 0))
+}
+
+// the closest safe spawn to a position, searching up to a distance away
+pub fn find_safe_near (world: &mut world::World, x: i32, y: i32, max_distance: i32) -> Option<(i32, i32)> {
+  for distance in 0..=max_distance {
+    for (offset_x, offset_y) in ring(distance) {
+      if is_safe_spawn(world, x + offset_x, y + offset_y) {
+        return Some((x + offset_x, y + offset_y));
+      }
+    }
+  }
+  None
+}
+
+// a tile is safe to spawn on if it and everything around it can be walked on without swimming
+pub fn is_safe_spawn (world: &mut world::World, x: i32, y: i32) -> bool {
+  for tile_x in (x - SPAWN_CLEARANCE)..=(x + SPAWN_CLEARANCE) {
+    for tile_y in (y - SPAWN_CLEARANCE)..=(y + SPAWN_CLEARANCE) {
+      let tile = world.get_tile(tile_x, tile_y);
+      if tile.solid || tile.swimmable {
+        return false;
+      }
+    }
+  }
+  true
+}
+
+// every offset that is exactly `distance` tiles from the origin (in a square)
+fn ring (distance: i32) -> Vec<(i32, i32)> {
+  if distance == 0 { return vec![(0, 0)] }
+  let mut tiles: Vec<(i32, i32)> = Vec::new();
+  for i in -distance..distance {
+    tiles.push((i, -distance)); // top
+    tiles.push((distance, i)); // right
+    tiles.push((-i, distance)); // bottom
+    tiles.push((-distance, -i)); // left
+  }
+  tiles
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  // generate worlds from a range of seeds, checking the player always starts somewhere safe
+  fn check_spawns (settings: WorldGenSettings, seeds: std::ops::Range<u32>) {
+    for seed in seeds {
+      let mut world = world::World::new(seed, settings.clone());
+      let (x, y) = find_spawn(&mut world);
+      assert!(is_safe_spawn(&mut world, x, y), "seed {} spawned at ({}, {}), which isn't safe", seed, x, y);
+    }
+  }
+
+  #[test]
+  fn spawns_are_safe_across_many_seeds () {
+    check_spawns(WorldGenSettings::default(), 0..100);
+  }
+
+  #[test]
+  fn spawns_are_safe_in_presets () {
+    check_spawns(WorldGenSettings::preset("archipelago").unwrap(), 1000..1025);
+    check_spawns(WorldGenSettings::preset("desert").unwrap(), 2000..2025);
+  }
+
+  #[test]
+  fn spawn_search_moves_out_from_deep_ocean () {
+    let mut world = world::World::flat(tiles::registry().tile("deep_ocean").id);
+    let grass = tiles::registry().tile("grass").id;
+    for x in 8..13 {
+      for y in -9..-4 {
+        world.set_tile(x, y, grass);
+      }
+    }
+    assert!(!is_safe_spawn(&mut world, 0, 0));
+    assert_eq!(find_spawn(&mut world), (9, -8));
+    assert_eq!(find_safe_near(&mut world, 0, 0, 5), None);
+  }
+
+  fn point (elevation: f64, temperature: f64, moisture: f64) -> NoisePoint {
+    NoisePoint { elevation, vegetation: 0., temperature, moisture }
+  }
+
+  #[test]
+  fn the_same_seed_gives_the_same_biomes () {
+    let settings = WorldGenSettings::default();
+    for (x, y) in [(0, 0), (17, -250), (-1000, 3000), (64, 64)] {
+      assert_eq!(biome_at(77, &settings, x, y), biome_at(77, &settings, x, y));
+    }
+    let seed_77 = (0..50).map(|x| biome_at(77, &settings, x * 40, 0)).collect::<Vec<_>>();
+    assert_eq!(seed_77, (0..50).map(|x| biome_at(77, &settings, x * 40, 0)).collect::<Vec<_>>());
+    assert_ne!(seed_77, (0..50).map(|x| biome_at(78, &settings, x * 40, 0)).collect::<Vec<_>>());
+  }
+
+  #[test]
+  fn noise_falls_into_the_expected_bands () {
+    let settings = WorldGenSettings::default();
+    let bands = [
+      (point(-0.5, 0., 0.), Biome::DeepOcean),
+      (point(-0.1, 0., 0.), Biome::Ocean),
+      (point(0.1, 0., 0.), Biome::Beach),
+      (point(0.5, -0.5, 0.), Biome::Tundra),
+      (point(0.5, 0.5, -0.5), Biome::Desert),
+      (point(0.5, 0.5, 0.), Biome::Grassland),
+      (point(0.5, 0.5, 0.5), Biome::Jungle),
+      (point(0.5, 0., -0.5), Biome::Grassland),
+      (point(0.5, 0., 0.), Biome::Forest),
+      (point(0.5, 0., 0.5), Biome::Swamp)
+    ];
+    for (point, biome) in bands {
+      assert_eq!(Biome::from_noise(&point, &settings), biome, "{:?}", point);
+    }
+  }
+
+  #[test]
+  fn offsets_move_the_boundaries () {
+    let default = WorldGenSettings::default();
+    let hotter = WorldGenSettings { temperature_offset: 0.3, ..WorldGenSettings::default() };
+    let wetter = WorldGenSettings { moisture_offset: 0.3, ..WorldGenSettings::default() };
+    let higher_sea = WorldGenSettings { sea_level: 0.3, ..WorldGenSettings::default() };
+    assert_eq!(Biome::from_noise(&point(0.5, 0., 0.), &default), Biome::Forest);
+    assert_eq!(Biome::from_noise(&point(0.5, 0., 0.), &hotter), Biome::Grassland);
+    assert_eq!(Biome::from_noise(&point(0.5, -0.3, 0.), &hotter), Biome::Forest);
+    assert_eq!(Biome::from_noise(&point(0.5, 0., 0.), &wetter), Biome::Swamp);
+    assert_eq!(Biome::from_noise(&point(0.5, 0.5, 0.), &wetter), Biome::Jungle);
+    assert_eq!(Biome::from_noise(&point(0.1, 0., 0.), &higher_sea), Biome::Ocean);
+    assert_eq!(Biome::from_noise(&point(0.4, 0., 0.), &higher_sea), Biome::Beach);
+  }
+
+  // the water in a few regions around the origin, sorted so they can be compared
+  fn water (hydrology: &mut Hydrology) -> Vec<((i32, i32), u16)> {
+    let mut water: Vec<((i32, i32), u16)> = (-1..=1).flat_map(|x| (-1..=1).map(move |y| (x, y)))
+      .flat_map(|region| hydrology.gen_region(region))
+      .collect();
+    water.sort();
+    water
+  }
+
+  #[test]
+  fn rivers_are_the_same_for_the_same_seed () {
+    let mut hydrology = Hydrology::new(5, WorldGenSettings::default());
+    let first = water(&mut hydrology);
+    assert!(!first.is_empty(), "no water to compare");
+    // again from the cached rivers, and from scratch
+    assert_eq!(water(&mut hydrology), first);
+    assert_eq!(water(&mut Hydrology::new(5, WorldGenSettings::default())), first);
+    assert_ne!(water(&mut Hydrology::new(6, WorldGenSettings::default())), first);
+  }
+
+  #[test]
+  fn rivers_flow_downhill_into_the_sea_or_a_lake () {
+    let hydrology = Hydrology::new(5, WorldGenSettings::default());
+    let layers = &hydrology.layers;
+    let mut rivers = 0;
+    for region_x in -2..=2 {
+      for region_y in -2..=2 {
+        for (source_x, source_y) in hydrology.sources(region_x, region_y) {
+          let (path, lake) = hydrology.trace_river(source_x, source_y);
+          assert!(!path.is_empty() && path.len() <= MAX_RIVER_LENGTH as usize);
+          for pair in path.windows(2) {
+            let ((x, y), (next_x, next_y)) = (pair[0], pair[1]);
+            assert_eq!((x - next_x).abs() + (y - next_y).abs(), 1);
+            assert!(layers.elevation(next_x, next_y) < layers.elevation(x, y), "river from ({}, {}) goes uphill", source_x, source_y);
+          }
+          if lake.is_empty() {
+            // the next step down is the ocean
+            let (x, y) = *path.last().unwrap();
+            let lowest = [(1, 0), (-1, 0), (0, 1), (0, -1)].iter().map(|(dx, dy)| layers.elevation(x + dx, y + dy)).fold(f64::MAX, f64::min);
+            assert!(lowest < layers.settings.sea_level, "river from ({}, {}) stops before the sea", source_x, source_y);
+          }
+          rivers += 1;
+        }
+      }
+    }
+    assert!(rivers > 0, "no rivers to check");
+  }
+
+}
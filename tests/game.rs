@@ -0,0 +1,357 @@
+use worldxplore::{game::{Game, InputEvent, Script}, gamepad, input::Action, inventory, player, rules, tiles, world::World};
+use winit::event::VirtualKeyCode;
+
+fn grass_world () -> Game<'static> {
+  Game::with_world(World::flat(tiles::registry().tile("grass").id), 0, 0)
+}
+
+#[test]
+fn walking_right_on_grass () {
+  let mut game = grass_world();
+  game.run(&Script::new().hold(0, Action::MoveRight, 100), 100);
+  assert!((game.player.x - 11.9).abs() < 0.1, "ended up at {}", game.player.x);
+  assert!((game.player.y - 0.5).abs() < 0.001);
+  assert!(!game.player.is_swimming);
+  assert_eq!(game.player.facing, (1, 0));
+  assert_eq!(game.ticks, 100);
+}
+
+#[test]
+fn letting_go_stops_the_player () {
+  let mut game = grass_world();
+  game.run(&Script::new().hold(0, Action::MoveDown, 30), 100);
+  let stopped_at = game.player.y;
+  game.run(&Script::new(), 30);
+  // the player slows down to a stop rather than stopping dead, so give them a moment
+  assert!(stopped_at > 1.);
+  assert!((game.player.y - stopped_at).abs() < 0.001);
+}
+
+#[test]
+fn walking_into_the_ocean_swims () {
+  let mut game = grass_world();
+  let ocean = tiles::registry().tile("ocean").id;
+  for x in 3..40 {
+    for y in -3..4 {
+      game.world.set_tile(x, y, ocean);
+    }
+  }
+  game.run(&Script::new().hold(0, Action::MoveRight, 60), 60);
+  assert!(game.player.x > 4.);
+  assert!(game.player.is_swimming);
+}
+
+#[test]
+fn a_paused_game_doesnt_move () {
+  let mut game = grass_world();
+  game.handle(InputEvent::ActionPressed(Action::Pause));
+  assert!(game.player.paused);
+  game.run(&Script::new().hold(0, Action::MoveRight, 100), 100);
+  assert_eq!((game.player.x, game.player.y), (0.5, 0.5));
+  assert_eq!(game.player.time, 0.);
+}
+
+#[test]
+fn harvest_drops_that_dont_fit_are_left_in_a_pile () {
+  let mut game = grass_world();
+  let registry = tiles::registry();
+  game.world.set_tile(0, 2, registry.tile("cactus").id);
+  // one slot, already taken by something that doesn't stack
+  game.player.inventory = inventory::Inventory::new(1);
+  game.player.inventory.add(inventory::ItemStack::new(registry.item("sword"), 1));
+  // a cactus takes two hits by hand
+  game.player.interact(&mut game.world, 0, 2);
+  game.player.interact(&mut game.world, 0, 2);
+  assert_eq!(game.world.get_tile_id(0, 2), registry.tile("item_pile").id);
+  let pile = game.world.pick_up_pile(0, 2).unwrap();
+  assert_eq!(pile[0].item.name, "Cactus");
+  assert_eq!(game.world.get_tile_id(0, 2), registry.tile("grass").id);
+}
+
+#[test]
+fn pause_stops_rebinding_instead_of_being_bound () {
+  let mut game = grass_world();
+  game.player.rebinding = Some(Action::Interact);
+  game.handle(InputEvent::Bind(Action::Interact, VirtualKeyCode::Escape));
+  assert_eq!(game.player.rebinding, None);
+  assert_eq!(game.player.bindings.keys(Action::Pause), [VirtualKeyCode::Escape]);
+  assert_eq!(game.player.bindings.keys(Action::Interact), [VirtualKeyCode::F]);
+  game.player.rebinding = Some(Action::Interact);
+  game.handle(InputEvent::Bind(Action::Interact, VirtualKeyCode::Space));
+  assert_eq!(game.player.bindings.keys(Action::Interact), [VirtualKeyCode::Space, VirtualKeyCode::F]);
+}
+
+#[test]
+fn a_fake_controller_walks_the_player () {
+  let mut game = grass_world();
+  let mut pad = gamepad::FakeGamepad::new();
+  pad.tilt(1., 0.);
+  for _ in 0..60 {
+    game.poll_gamepad(&mut pad);
+    game.tick();
+  }
+  assert!(game.player.x > 5.);
+  assert_eq!(game.player.facing, (1, 0));
+}
+
+#[test]
+fn unplugging_the_controller_lets_go_of_everything () {
+  let mut game = grass_world();
+  let mut pad = gamepad::FakeGamepad::new();
+  pad.press(gamepad::Button::DPadRight);
+  pad.tilt(0., 1.);
+  game.poll_gamepad(&mut pad);
+  assert!(game.player.actions_held.contains(&Action::MoveRight));
+  assert_ne!(game.player.stick, (0., 0.));
+  pad.connected = false;
+  game.poll_gamepad(&mut pad);
+  assert!(game.player.actions_held.is_empty());
+  assert_eq!(game.player.stick, (0., 0.));
+}
+
+#[test]
+fn a_cactus_hurts_once_and_pushes_the_player_away () {
+  let mut game = grass_world();
+  game.world.set_tile(1, 0, tiles::registry().tile("cactus").id);
+  game.tick();
+  assert_eq!(game.player.health, 5.);
+  assert_eq!(game.player.last_hurt_by, "Cactus");
+  game.run(&Script::new(), 10);
+  assert!(game.player.x < 0.3, "only pushed back to {}", game.player.x);
+  // keep walking into it, which can't hurt again until the player stops being invulnerable
+  game.handle(InputEvent::ActionPressed(Action::MoveRight));
+  game.run(&Script::new(), player::INVULNERABLE_TICKS as u64 - 12);
+  assert_eq!(game.player.health, 5.);
+  game.run(&Script::new(), 20);
+  assert_eq!(game.player.health, 4.);
+}
+
+#[test]
+fn slowing_tiles_lower_the_walking_speed () {
+  // walk long enough to settle at full speed, and see how far one more second goes
+  let speed_on = |name| {
+    let mut game = Game::with_world(World::flat(tiles::registry().tile(name).id), 0, 0);
+    game.handle(InputEvent::ActionPressed(Action::MoveRight));
+    game.run(&Script::new(), 100);
+    let settled_at = game.player.x;
+    game.run(&Script::new(), 60);
+    game.player.x - settled_at
+  };
+  let (grass, swamp) = (speed_on("grass"), speed_on("swamp_grass"));
+  assert!((swamp / grass - 0.75).abs() < 0.01, "walked {} on grass and {} in the swamp", grass, swamp);
+}
+
+// give the player some things to lose, a campfire to come back to, and then take away all their health
+fn die_with (penalty: rules::DeathPenalty) -> Game<'static> {
+  let mut game = grass_world();
+  let registry = tiles::registry();
+  game.player.rules.death_penalty = penalty;
+  game.player.rules.respawn_health = 0.5;
+  game.player.inventory = inventory::Inventory::new(inventory::INVENTORY_SLOTS);
+  game.player.inventory.add(inventory::ItemStack::new(registry.item("log"), 7));
+  game.player.inventory.add(inventory::ItemStack::new(registry.item("sword"), 1));
+  game.world.set_tile(20, 0, registry.tile("campfire").id);
+  game.player.respawn_point = Some((20, 0));
+  game.player.hurt(6., "Testing");
+  game.tick();
+  assert!(game.player.dead);
+  assert!(game.player.paused);
+  assert_eq!(game.player.death_stats.deaths, 1);
+  game
+}
+
+fn items (inventory: &inventory::Inventory<'static>) -> Vec<(&'static str, u32)> {
+  inventory.slots.iter().flatten().map(|stack| (stack.item.name, stack.count)).collect()
+}
+
+// the player comes back next to the campfire, with the health the rules say and full meters
+fn check_respawn (game: &mut Game<'static>) {
+  game.player.respawn(&mut game.world);
+  let (x, y) = game.player.feet_tile();
+  assert!(!game.player.dead && !game.player.paused);
+  assert!((x - 20).abs() <= player::RESPAWN_SEARCH_DISTANCE && y.abs() <= player::RESPAWN_SEARCH_DISTANCE, "came back at ({}, {})", x, y);
+  assert_eq!(game.player.health, 3.);
+  assert_eq!(game.player.hunger, player::MAX_METER);
+}
+
+#[test]
+fn dying_keeps_the_inventory_when_the_rules_say_to () {
+  let mut game = die_with(rules::DeathPenalty::KeepInventory);
+  assert_eq!(items(&game.player.inventory), vec![("Log", 7), ("Sword", 1)]);
+  assert!(game.world.pick_up_pile(0, 0).is_none());
+  check_respawn(&mut game);
+}
+
+#[test]
+fn dying_drops_the_inventory_in_a_pile () {
+  let mut game = die_with(rules::DeathPenalty::DropInventory);
+  assert!(items(&game.player.inventory).is_empty());
+  let pile = game.world.pick_up_pile(0, 0).unwrap();
+  assert_eq!(pile.iter().map(|stack| (stack.item.name, stack.count)).collect::<Vec<_>>(), vec![("Log", 7), ("Sword", 1)]);
+  check_respawn(&mut game);
+}
+
+#[test]
+fn dying_loses_the_inventory_for_good () {
+  let mut game = die_with(rules::DeathPenalty::LoseInventory);
+  assert!(items(&game.player.inventory).is_empty());
+  assert!(game.world.pick_up_pile(0, 0).is_none());
+  check_respawn(&mut game);
+}
+
+#[test]
+fn without_a_campfire_the_player_comes_back_where_they_started () {
+  let mut game = die_with(rules::DeathPenalty::KeepInventory);
+  game.world.set_tile(20, 0, tiles::registry().tile("grass").id);
+  game.player.respawn(&mut game.world);
+  assert_eq!(game.player.feet_tile(), game.player.spawn_point);
+  assert_eq!(game.player.respawn_point, None);
+}
+
+#[test]
+fn respawning_with_less_than_a_heart_of_max_health_doesnt_crash () {
+  let mut game = die_with(rules::DeathPenalty::KeepInventory);
+  game.player.max_health = 0.5;
+  game.player.respawn(&mut game.world);
+  assert_eq!(game.player.health, 1.);
+}
+
+fn close (a: f32, b: f32) -> bool {
+  (a - b).abs() < 0.001
+}
+
+#[test]
+fn hunger_and_thirst_drain_over_time () {
+  let mut game = grass_world();
+  let rules = game.player.rules.clone();
+  game.run(&Script::new(), 600);
+  assert!(close(game.player.hunger, player::MAX_METER - rules.hunger_rate * 10.), "hunger is {}", game.player.hunger);
+  assert!(close(game.player.thirst, player::MAX_METER - rules.thirst_rate * 10.), "thirst is {}", game.player.thirst);
+  assert_eq!(game.player.health, 6.);
+}
+
+#[test]
+fn sprinting_uses_stamina_which_comes_back () {
+  let mut game = grass_world();
+  let rules = game.player.rules.clone();
+  game.handle(InputEvent::ActionPressed(Action::MoveRight));
+  game.handle(InputEvent::ActionPressed(Action::Sprint));
+  game.run(&Script::new(), 60);
+  assert!(close(game.player.stamina, player::MAX_METER - rules.sprint_stamina_rate), "stamina is {}", game.player.stamina);
+  assert!(close(game.player.hunger, player::MAX_METER - rules.hunger_rate - rules.sprint_hunger_rate));
+  let tired = game.player.stamina;
+  game.handle(InputEvent::ActionReleased(Action::MoveRight));
+  game.handle(InputEvent::ActionReleased(Action::Sprint));
+  game.run(&Script::new(), 60);
+  assert!(close(game.player.stamina, tired + rules.stamina_recovery_rate), "stamina is {}", game.player.stamina);
+}
+
+#[test]
+fn eating_fills_up_and_uses_the_food () {
+  let mut game = grass_world();
+  game.player.inventory = inventory::Inventory::new(inventory::INVENTORY_SLOTS);
+  game.player.inventory.add(inventory::ItemStack::new(tiles::registry().item("cactus_piece"), 2));
+  game.player.selected = Some(0);
+  game.player.hunger = 5.;
+  game.player.thirst = 5.;
+  game.handle(InputEvent::ActionPressed(Action::Eat));
+  // a cactus piece is worth 1 hunger and 2 thirst
+  assert_eq!((game.player.hunger, game.player.thirst), (6., 7.));
+  assert_eq!(game.player.inventory.get(0).unwrap().count, 1);
+  game.handle(InputEvent::ActionPressed(Action::Eat));
+  assert!(game.player.inventory.get(0).is_none());
+  assert_eq!(game.player.selected, None);
+  // nothing left to eat
+  assert!(!game.player.eat());
+  assert_eq!(game.player.hunger, 7.);
+}
+
+#[test]
+fn only_fresh_water_can_be_drunk () {
+  let mut game = grass_world();
+  let registry = tiles::registry();
+  game.world.set_tile(0, 1, registry.tile("ocean").id);
+  game.world.set_tile(1, 0, registry.tile("river").id);
+  game.player.thirst = 5.;
+  game.player.interact(&mut game.world, 0, 1);
+  assert_eq!(game.player.thirst, 5.);
+  game.player.interact(&mut game.world, 1, 0);
+  assert_eq!(game.player.thirst, 5. + game.player.rules.drink_amount);
+  assert_eq!(game.world.get_tile_id(1, 0), registry.tile("river").id);
+}
+
+#[test]
+fn empty_meters_take_health () {
+  let mut game = grass_world();
+  let damage = game.player.rules.empty_meter_damage;
+  game.player.hunger = 0.;
+  game.run(&Script::new(), 60);
+  assert!(close(game.player.health, 6. - damage), "health is {}", game.player.health);
+  assert_eq!(game.player.last_hurt_by, "Hunger");
+  game.player.hunger = player::MAX_METER;
+  game.player.thirst = 0.;
+  game.run(&Script::new(), 60);
+  assert!(close(game.player.health, 6. - 2. * damage));
+  assert_eq!(game.player.last_hurt_by, "Thirst");
+  // full meters don't hurt
+  game.player.thirst = player::MAX_METER;
+  game.run(&Script::new(), 60);
+  assert!(close(game.player.health, 6. - 2. * damage));
+}
+
+// hold a tool in the first slot, with however much durability it has left
+fn hold_tool (game: &mut Game<'static>, name: &str, durability: u32) {
+  game.player.inventory = inventory::Inventory::new(inventory::INVENTORY_SLOTS);
+  let tool = inventory::ItemStack::new(tiles::registry().item(name), 1);
+  game.player.inventory.slots[0] = Some(inventory::ItemStack { durability: Some(durability), ..tool });
+  game.player.selected = Some(0);
+}
+
+// how many hits it takes to harvest a tile, giving up after 10
+fn hits_to_harvest (game: &mut Game<'static>, tile: &str) -> Option<u32> {
+  let registry = tiles::registry();
+  game.world.set_tile(0, 1, registry.tile(tile).id);
+  (1..=10).find(|_| {
+    game.player.interact(&mut game.world, 0, 1);
+    game.world.get_tile_id(0, 1) != registry.tile(tile).id
+  })
+}
+
+#[test]
+fn an_axe_chops_trees_faster_than_bare_hands () {
+  let mut game = grass_world();
+  assert_eq!(hits_to_harvest(&mut game, "tree"), Some(4));
+  hold_tool(&mut game, "axe", 80);
+  assert_eq!(hits_to_harvest(&mut game, "tree"), Some(2));
+  assert_eq!(game.world.get_tile_id(0, 1), tiles::registry().tile("stump").id);
+}
+
+#[test]
+fn rock_needs_a_pickaxe () {
+  let mut game = grass_world();
+  assert_eq!(hits_to_harvest(&mut game, "rock"), None);
+  hold_tool(&mut game, "axe", 80);
+  assert_eq!(hits_to_harvest(&mut game, "rock"), None);
+  // the wrong tool isn't worn down by hitting something it can't harvest
+  assert_eq!(game.player.inventory.get(0).unwrap().durability, Some(80));
+  hold_tool(&mut game, "pickaxe", 80);
+  assert_eq!(hits_to_harvest(&mut game, "rock"), Some(2));
+}
+
+#[test]
+fn tools_wear_down_and_break () {
+  let mut game = grass_world();
+  hold_tool(&mut game, "axe", 2);
+  game.world.set_tile(0, 1, tiles::registry().tile("tree").id);
+  game.player.interact(&mut game.world, 0, 1);
+  assert_eq!(game.player.inventory.get(0).unwrap().durability, Some(1));
+  game.player.interact(&mut game.world, 0, 1);
+  // the logs from the tree go where the axe was
+  assert_eq!(game.player.inventory.count(tiles::registry().item("axe").id), 0);
+  assert_eq!(game.player.selected, None);
+  assert_eq!(game.world.get_tile_id(0, 1), tiles::registry().tile("stump").id);
+  // the player is told, and the message goes away after a while
+  assert_eq!(game.player.message, "Axe broke");
+  game.run(&Script::new(), player::MESSAGE_TICKS as u64);
+  assert_eq!(game.player.message_ticks, 0);
+}
@@ -0,0 +1,86 @@
+use worldxplore::{game::Game, inventory, rules, save, tiles, world::{self, World}, worldgen};
+
+// save a game, load that save into a different game and save it again, returning both saves and the loaded game
+fn round_trip (game: &mut Game) -> (String, String, Game<'static>) {
+  let saved = save::to_yaml(&game.player.gamedata(&mut game.world)).unwrap();
+  let mut loaded = Game::with_world(World::flat(tiles::registry().tile("sand").id), 100, 100);
+  let skipped = loaded.player.load_gamedata(save::from_yaml(&saved).unwrap(), &mut loaded.world);
+  assert!(skipped.is_empty(), "{}", skipped);
+  let saved_again = save::to_yaml(&loaded.player.gamedata(&mut loaded.world)).unwrap();
+  (saved, saved_again, loaded)
+}
+
+#[test]
+fn generated_world_with_harvested_and_placed_tiles () {
+  let mut game = Game::new(42, worldgen::WorldGenSettings::default(), rules::GameRules::default());
+  let (spawn_x, spawn_y) = game.player.spawn_point;
+  // harvest everything that can be harvested near the spawn, and build a few walls
+  let mut harvested = 0;
+  for x in spawn_x - 16..spawn_x + 16 {
+    for y in spawn_y - 16..spawn_y + 16 {
+      if game.world.harvest(x, y).is_some() {
+        harvested += 1;
+      }
+    }
+  }
+  assert!(harvested > 0, "nothing to harvest near the spawn");
+  let wall = tiles::registry().tile("wood_wall").id;
+  for x in 0..3 {
+    game.world.set_tile(spawn_x + 20 + x, spawn_y, wall);
+  }
+  let (saved, saved_again, mut loaded) = round_trip(&mut game);
+  assert_eq!(saved, saved_again);
+  assert_eq!(loaded.world.get_tile_id(spawn_x + 21, spawn_y), wall);
+  assert_eq!(loaded.player.spawn_point, (spawn_x, spawn_y));
+}
+
+#[test]
+fn flat_world_stays_flat () {
+  let grass = tiles::registry().tile("grass").id;
+  let mut game = Game::with_world(World::flat(grass), 0, 0);
+  game.world.set_tile(5, -40, tiles::registry().tile("campfire").id);
+  let (saved, saved_again, mut loaded) = round_trip(&mut game);
+  assert_eq!(saved, saved_again);
+  assert_eq!(loaded.world.flat, Some(grass));
+  assert_eq!(loaded.world.get_tile_id(1000, -1000), grass);
+  assert_eq!(loaded.world.get_tile_id(5, -40), tiles::registry().tile("campfire").id);
+}
+
+#[test]
+fn item_piles_keep_their_items () {
+  let mut game = Game::with_world(World::flat(tiles::registry().tile("grass").id), 0, 0);
+  let registry = tiles::registry();
+  game.world.drop_pile(3, 4, vec![inventory::ItemStack::new(registry.item("log"), 12), inventory::ItemStack::new(registry.item("stick"), 3)]);
+  let (saved, saved_again, mut loaded) = round_trip(&mut game);
+  assert_eq!(saved, saved_again);
+  assert_eq!(loaded.world.get_tile_id(3, 4), registry.tile("item_pile").id);
+  let items = loaded.world.pick_up_pile(3, 4).unwrap();
+  assert_eq!(items.iter().map(|stack| (stack.item.name, stack.count)).collect::<Vec<_>>(), vec![("Log", 12), ("Stick", 3)]);
+  assert_eq!(loaded.world.get_tile_id(3, 4), registry.tile("grass").id);
+}
+
+#[test]
+fn worn_tools_keep_their_durability () {
+  let mut game = Game::with_world(World::flat(tiles::registry().tile("grass").id), 0, 0);
+  let axe = inventory::ItemStack { durability: Some(37), ..inventory::ItemStack::new(tiles::registry().item("axe"), 1) };
+  game.player.inventory = inventory::Inventory::new(inventory::INVENTORY_SLOTS);
+  game.player.inventory.slots[5] = Some(axe);
+  let (saved, saved_again, loaded) = round_trip(&mut game);
+  assert_eq!(saved, saved_again);
+  let loaded_axe = loaded.player.inventory.get(5).unwrap();
+  assert_eq!((loaded_axe.item.name, loaded_axe.durability), ("Axe", Some(37)));
+}
+
+#[test]
+fn unknown_tiles_and_items_are_left_out_and_reported () {
+  let mut game = Game::with_world(World::flat(tiles::registry().tile("sand").id), 0, 0);
+  let mut gamedata = game.player.gamedata(&mut game.world);
+  let change = world::TileChange { x: 1, y: 1, id: 900, state: Default::default() };
+  gamedata.chunks = vec![world::ChunkChanges { chunk_x: 0, chunk_y: 0, changes: vec![change] }];
+  gamedata.inventory = Some(vec![inventory::SavedStack { slot: 0, item: 901, count: 1, durability: None }]);
+  let skipped = game.player.load_gamedata(gamedata, &mut game.world);
+  assert_eq!(skipped, save::Skipped { tiles: vec![900], items: vec![901] });
+  assert_eq!(skipped.to_string(), "left out unknown tiles 900 and items 901");
+  assert_eq!(game.world.get_tile_id(1, 1), tiles::registry().tile("sand").id);
+  assert!(game.player.inventory.get(0).is_none());
+}
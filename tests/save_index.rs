@@ -0,0 +1,173 @@
+use std::fs;
+use std::path::PathBuf;
+use worldxplore::{game::Game, save, tiles, world::World};
+
+// an empty directory of its own for each test, which is removed again when the test is done with it
+struct TempDir(PathBuf);
+
+impl TempDir {
+  fn new (name: &str) -> Self {
+    let dir = std::env::temp_dir().join(format!("worldxplore-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    TempDir(dir)
+  }
+}
+
+impl Drop for TempDir {
+  fn drop (&mut self) {
+    let _ = fs::remove_dir_all(&self.0);
+  }
+}
+
+// save a flat world with a seed under a name
+fn save_world (saves: &save::SaveIndex, name: &str, seed: u32) {
+  let mut game = Game::with_world(World::flat(tiles::registry().tile("grass").id), 0, 0);
+  let mut gamedata = game.player.gamedata(&mut game.world);
+  gamedata.seed = seed;
+  saves.write(name, &gamedata).unwrap();
+}
+
+fn names (saves: &save::SaveIndex) -> Vec<String> {
+  let mut names: Vec<String> = saves.list().unwrap().into_iter().map(|info| info.name).collect();
+  names.sort();
+  names
+}
+
+#[test]
+fn listing_shows_every_world () {
+  let dir = TempDir::new("list");
+  let saves = save::SaveIndex::new(&dir.0);
+  assert!(saves.list().unwrap().is_empty());
+  save_world(&saves, "First", 1);
+  save_world(&saves, "Second", 2);
+  fs::write(dir.0.join("Broken.yaml"), "- not\n- a save\n").unwrap();
+  fs::write(dir.0.join("Leftover.yaml.tmp"), "").unwrap();
+  let worlds = saves.list().unwrap();
+  assert_eq!(names(&saves), ["Broken", "First", "Second"]);
+  let second = worlds.iter().find(|info| info.name == "Second").unwrap();
+  assert_eq!((second.seed, second.problem.clone()), (2, None));
+  assert!(worlds.iter().find(|info| info.name == "Broken").unwrap().problem.is_some());
+}
+
+#[test]
+fn listing_a_missing_directory_is_empty () {
+  let dir = TempDir::new("missing");
+  assert!(save::SaveIndex::new(dir.0.join("nothing here")).list().unwrap().is_empty());
+}
+
+#[test]
+fn version_0_saves_are_listed_with_their_upgraded_play_time () {
+  let dir = TempDir::new("version-0");
+  fs::write(dir.0.join("Old.yaml"), include_str!("saves/v0.yaml")).unwrap();
+  let saves = save::SaveIndex::new(&dir.0);
+  let info = &saves.list().unwrap()[0];
+  let gamedata = saves.read("Old").unwrap();
+  assert_eq!((info.seed, info.play_time), (gamedata.seed, gamedata.time));
+}
+
+#[test]
+fn renaming_moves_the_world () {
+  let dir = TempDir::new("rename");
+  let saves = save::SaveIndex::new(&dir.0);
+  save_world(&saves, "Old Name", 5);
+  saves.rename("Old Name", "New Name").unwrap();
+  assert_eq!(names(&saves), ["New Name"]);
+  assert_eq!(saves.read("New Name").unwrap().seed, 5);
+}
+
+#[test]
+fn duplicating_keeps_both () {
+  let dir = TempDir::new("duplicate");
+  let saves = save::SaveIndex::new(&dir.0);
+  save_world(&saves, "World", 5);
+  saves.duplicate("World", "World 2").unwrap();
+  assert_eq!(names(&saves), ["World", "World 2"]);
+  assert_eq!(saves.read("World 2").unwrap(), saves.read("World").unwrap());
+}
+
+#[test]
+fn deleting_removes_the_world () {
+  let dir = TempDir::new("delete");
+  let saves = save::SaveIndex::new(&dir.0);
+  save_world(&saves, "World", 5);
+  saves.delete("World").unwrap();
+  assert!(!saves.exists("World"));
+  assert!(matches!(saves.delete("World"), Err(save::SaveError::NotFound { .. })));
+}
+
+#[test]
+fn missing_worlds_arent_found () {
+  let dir = TempDir::new("not-found");
+  let saves = save::SaveIndex::new(&dir.0);
+  assert!(matches!(saves.read("Nowhere"), Err(save::SaveError::NotFound { .. })));
+  assert!(matches!(saves.rename("Nowhere", "Somewhere"), Err(save::SaveError::NotFound { .. })));
+  assert!(matches!(saves.duplicate("Nowhere", "Somewhere"), Err(save::SaveError::NotFound { .. })));
+}
+
+#[test]
+fn names_in_use_are_refused () {
+  let dir = TempDir::new("name-taken");
+  let saves = save::SaveIndex::new(&dir.0);
+  save_world(&saves, "One", 1);
+  save_world(&saves, "Two", 2);
+  match saves.rename("One", "Two") {
+    Err(save::SaveError::NameTaken { name }) => assert_eq!(name, "Two"),
+    other => panic!("expected the name to be taken, got {:?}", other)
+  }
+  assert!(matches!(saves.duplicate("One", "Two"), Err(save::SaveError::NameTaken { .. })));
+  // neither world was touched
+  assert_eq!(saves.read("Two").unwrap().seed, 2);
+  assert_eq!(saves.read("One").unwrap().seed, 1);
+}
+
+#[test]
+fn unused_names_count_up () {
+  let dir = TempDir::new("unused-name");
+  let saves = save::SaveIndex::new(&dir.0);
+  assert_eq!(saves.unused_name("New Game"), "New Game");
+  save_world(&saves, "New Game", 1);
+  assert_eq!(saves.unused_name("New Game"), "New Game 2");
+  save_world(&saves, "New Game 2", 2);
+  assert_eq!(saves.unused_name("New Game"), "New Game 3");
+}
+
+#[test]
+fn names_have_to_work_as_file_names () {
+  for name in ["World", "My World 2", "Ünïcode", "a.b"] {
+    assert!(save::SaveIndex::check_name(name).is_ok(), "{:?} should be allowed", name);
+  }
+  for name in ["", " World", "World ", ".hidden", "World.", "a/b", "a\\b", "what?", "C:", "tab\there"] {
+    assert!(matches!(save::SaveIndex::check_name(name), Err(save::SaveError::InvalidName { .. })), "{:?} should be refused", name);
+  }
+  let dir = TempDir::new("invalid-name");
+  let saves = save::SaveIndex::new(&dir.0);
+  save_world(&saves, "World", 1);
+  assert!(matches!(saves.rename("World", "../World"), Err(save::SaveError::InvalidName { .. })));
+}
+
+#[test]
+fn a_failed_save_leaves_the_old_one_whole () {
+  let dir = TempDir::new("failed-save");
+  let saves = save::SaveIndex::new(&dir.0);
+  save_world(&saves, "World", 1);
+  let before = fs::read_to_string(saves.path("World")).unwrap();
+  // something already in the way of the temporary file stops the save before it starts
+  fs::create_dir(dir.0.join("World.yaml.tmp")).unwrap();
+  let mut gamedata = saves.read("World").unwrap();
+  gamedata.seed = 2;
+  assert!(matches!(saves.write("World", &gamedata), Err(save::SaveError::Io { .. })));
+  assert_eq!(fs::read_to_string(saves.path("World")).unwrap(), before);
+}
+
+#[test]
+fn a_failed_save_cleans_up_after_itself () {
+  let dir = TempDir::new("failed-rename");
+  let saves = save::SaveIndex::new(&dir.0);
+  // a directory where the save goes can't be replaced by the finished save
+  fs::create_dir_all(saves.path("World").join("in the way")).unwrap();
+  let mut game = Game::with_world(World::flat(tiles::registry().tile("grass").id), 0, 0);
+  assert!(matches!(saves.write("World", &game.player.gamedata(&mut game.world)), Err(save::SaveError::Io { .. })));
+  assert!(!dir.0.join("World.yaml.tmp").exists());
+  assert_eq!(fs::read_dir(&dir.0).unwrap().count(), 1);
+}
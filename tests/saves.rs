@@ -0,0 +1,65 @@
+use worldxplore::{player, rules, save, world, worldgen};
+use player::GameData;
+
+// a save with nothing but what every save has, as the current version of the game would read it
+fn expected (position: (f32, f32), health: f32, seed: u32, time: f32) -> GameData {
+  GameData {
+    version: save::SAVE_VERSION,
+    position, health, max_health: 6., seed, time,
+    settings: worldgen::WorldGenSettings::default(),
+    chunks: Vec::new(),
+    flat: None,
+    inventory: None,
+    piles: Vec::new(),
+    rules: rules::GameRules::default(),
+    death_stats: player::DeathStats::default(),
+    spawn_point: None,
+    respawn_point: None,
+    hunger: player::MAX_METER, thirst: player::MAX_METER, stamina: player::MAX_METER
+  }
+}
+
+fn change (x: i32, y: i32, damage: u8) -> world::TileChange {
+  world::TileChange { x, y, id: 14, state: world::TileState { damage, ..world::TileState::default() } }
+}
+
+#[test]
+fn version_0_moves_the_camera_corner_to_the_feet_and_rescales_time () {
+  let gamedata = save::from_yaml(include_str!("saves/v0.yaml")).unwrap();
+  assert_eq!(gamedata, expected((499.5, 526.5), 6., 1234, 3.5 * player::DAY_LENGTH / 36.));
+}
+
+#[test]
+fn version_0_changed_tiles_are_grouped_by_chunk () {
+  let gamedata = save::from_yaml(include_str!("saves/v0_changed_tiles.yaml")).unwrap();
+  let chunks = vec![
+    world::ChunkChanges { chunk_x: -1, chunk_y: 0, changes: vec![change(31, 2, 1)] },
+    world::ChunkChanges { chunk_x: 0, chunk_y: 0, changes: vec![change(3, 4, 0)] },
+    world::ChunkChanges { chunk_x: 1, chunk_y: -1, changes: vec![change(8, 29, 0)] }
+  ];
+  assert_eq!(gamedata, GameData { chunks, ..expected((-3.5, 24.5), 4., 99, 20.) });
+}
+
+#[test]
+fn upgraded_saves_are_written_as_the_current_version () {
+  let gamedata = save::from_yaml(include_str!("saves/v0_changed_tiles.yaml")).unwrap();
+  let yaml = save::to_yaml(&gamedata).unwrap();
+  assert!(yaml.contains(&format!("version: {}", save::SAVE_VERSION)));
+  assert!(!yaml.contains("changed_tiles"));
+  assert_eq!(save::from_yaml(&yaml).unwrap(), gamedata);
+}
+
+#[test]
+fn saves_from_a_newer_version_are_refused () {
+  match save::from_yaml(include_str!("saves/v2.yaml")) {
+    Err(save::SaveError::FutureVersion { version }) => assert_eq!(version, 2),
+    other => panic!("expected a future version error, got {:?}", other)
+  }
+}
+
+#[test]
+fn files_that_arent_saves_are_refused () {
+  assert!(matches!(save::from_yaml("- just\n- a list\n"), Err(save::SaveError::NotASave)));
+  assert!(matches!(save::from_yaml("version: soon\n"), Err(save::SaveError::InvalidVersion)));
+  assert!(matches!(save::from_yaml("health: ["), Err(save::SaveError::Parse(_))));
+}